@@ -1,28 +1,59 @@
 // This is now just a test runner for our library
-use pico_args;
-use mobile_core::{emergency_commit_async, pair_async};
-use libp2p::Multiaddr;
+use mobile_core::{emergency_commit_async, pair_async, parse_pairing_uri, set_network_options, NetworkOptions};
 
 #[tokio::main]
 async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> anyhow::Result<()> {
     let mut args = pico_args::Arguments::from_env();
-    // 1. Run the daemon first and get its full address
-    let daemon_full_addr_str = "/ip4/172.20.128.55/tcp/35809/p2p/12D3KooWMzd2tGd9pWxQDQz6C9cy9QHaGLndPMLNjC7avBGJrp4F";
-    if args.contains("--pair") {
+    // The pairing URI `daemon pair-code` prints, with the daemon's addresses and a code
+    let uri: Option<String> = args.opt_value_from_str("--uri")?;
+    // Or the daemon's full addresses, comma separated, e.g. to commit after pairing
+    let addr: Option<String> = args.opt_value_from_str("--addr")?;
+    let pair_code: Option<String> = args.opt_value_from_str("--pair")?;
+    let remaining = args.finish();
+    if !remaining.is_empty() {
+        anyhow::bail!("Unexpected arguments: {:?}", remaining);
+    }
+
+    let (daemon_full_addr, code) = match (uri, addr) {
+        (Some(uri), None) => {
+            let info = parse_pairing_uri(uri)?;
+            if info.pre_shared_key.is_some() {
+                set_network_options(NetworkOptions {
+                    private_network: true,
+                    bootstrap_nodes: None,
+                    pre_shared_key: info.pre_shared_key,
+                })?;
+            }
+            (info.addresses.join(","), Some(pair_code.unwrap_or(info.code)))
+        }
+        (None, Some(addr)) => (addr, pair_code),
+        _ => anyhow::bail!("Pass either --uri <pairing URI> or --addr <daemon address>"),
+    };
+
+    if let Some(code) = code {
         println!("--- Running Client in Pairing Mode ---");
-        match pair_async(daemon_full_addr_str.to_string()).await {
+        match pair_async(".".to_string(), daemon_full_addr, code).await {
             Ok(_) => println!("✅ Pairing successful! Daemon has approved this client."),
             Err(e) => eprintln!("❌ Pairing failed: {}", e),
         }
     } else {
         println!("--- Running Client in Commit Mode ---");
-        let repo_path = "/tmp/test-repo".to_string();
+        // Alias of the repository in the daemon's repositories.json
+        let repo = "test-repo".to_string();
         let file_path = "README.md".to_string();
         let new_content = "This commit came from the new MOBILE CORE library!".to_string();
         let message = "refactor: Logic moved to mobile-core library".to_string();
         let result = emergency_commit_async(
-            daemon_full_addr_str.to_string(),
-            repo_path,
+            ".".to_string(),
+            daemon_full_addr,
+            repo,
             file_path,
             new_content,
             message,
@@ -38,4 +69,5 @@ async fn main() {
             }
         }
     }
-}
\ No newline at end of file
+    Ok(())
+}
@@ -0,0 +1,294 @@
+// Append-only audit log of every request the daemon decides on, kept as JSON lines in
+// audit.jsonl in the data directory. Each entry carries the hash of the one before it,
+// so editing or removing an entry breaks the chain and `daemon audit verify` notices.
+use anyhow::{Context, Result};
+use libp2p::PeerId;
+use protocol::{CommitRequest, CommitResponse, ErrorKind, FileChange};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::pairing::unix_now;
+
+const AUDIT_FILE: &str = "audit.jsonl";
+// What the first entry chains from
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Pair,
+    Commit,
+    ListRepos,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    // A pairing or repository list the daemon granted
+    Accepted,
+    Committed,
+    // The daemon tried to commit and couldn't
+    Failed,
+    // Refused by the registry, the peer's policy or a bad pairing code, before touching git
+    Rejected,
+    // The peer isn't trusted, so the request was ignored
+    Untrusted,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Decision::Accepted => "accepted",
+            Decision::Committed => "committed",
+            Decision::Failed => "failed",
+            Decision::Rejected => "rejected",
+            Decision::Untrusted => "untrusted",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Decision {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "accepted" => Ok(Decision::Accepted),
+            "committed" => Ok(Decision::Committed),
+            "failed" => Ok(Decision::Failed),
+            "rejected" => Ok(Decision::Rejected),
+            "untrusted" => Ok(Decision::Untrusted),
+            _ => Err(anyhow::anyhow!("Unknown decision {}, expected accepted, committed, failed, rejected or untrusted", name)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEntry {
+    // Unix seconds
+    pub timestamp: u64,
+    pub peer: String,
+    pub request_id: String,
+    pub action: Action,
+    pub repo: Option<String>,
+    pub branch: Option<String>,
+    // Every path the request changes, both sides of a rename
+    pub files: Vec<String>,
+    pub decision: Decision,
+    pub commit_hash: Option<String>,
+    // Why the request was rejected or failed, or why the push after a commit failed
+    pub reason: Option<String>,
+    // Hash of the previous entry, set when the entry is written
+    pub prev_hash: String,
+}
+
+impl AuditEntry {
+    pub fn new(peer: &PeerId, request_id: &str, action: Action, decision: Decision) -> Self {
+        Self {
+            timestamp: unix_now(),
+            peer: peer.to_string(),
+            request_id: request_id.to_string(),
+            action,
+            repo: None,
+            branch: None,
+            files: Vec::new(),
+            decision,
+            commit_hash: None,
+            reason: None,
+            prev_hash: String::new(),
+        }
+    }
+
+    // An entry for a commit request. It stays `Untrusted` until `with_response` records what happened.
+    pub fn commit(peer: &PeerId, request_id: &str, request: &CommitRequest) -> Self {
+        let files = request
+            .changes
+            .iter()
+            .flat_map(|change| match change {
+                FileChange::Write { path, .. } | FileChange::Delete { path } => vec![path.clone()],
+                FileChange::Rename { from, to } => vec![from.clone(), to.clone()],
+            })
+            .collect();
+        Self {
+            repo: Some(request.repo.clone()),
+            branch: request.branch.clone(),
+            files,
+            ..Self::new(peer, request_id, Action::Commit, Decision::Untrusted)
+        }
+    }
+
+    pub fn with_response(self, response: &CommitResponse) -> Self {
+        let decision = match (response.success, &response.error_kind) {
+            (true, _) => Decision::Committed,
+            (false, Some(ErrorKind::Forbidden)) => Decision::Rejected,
+            (false, _) => Decision::Failed,
+        };
+        let push_error = response.push_result.as_ref().and_then(|push| push.error_message.clone());
+        Self {
+            decision,
+            commit_hash: response.commit_hash.clone(),
+            reason: response.error_message.clone().or(push_error),
+            ..self
+        }
+    }
+
+    fn hash(&self) -> Result<String> {
+        let json = serde_json::to_vec(self)?;
+        Ok(format!("{:x}", Sha256::digest(json)))
+    }
+}
+
+// One line of audit.jsonl
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuditRecord {
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    // SHA-256 of the entry's JSON, which includes `prev_hash`
+    pub hash: String,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    last_hash: String,
+}
+
+impl AuditLog {
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(AUDIT_FILE);
+        let last_hash = match read_records(&path)?.pop() {
+            Some(record) => record.hash,
+            None => GENESIS_HASH.to_string(),
+        };
+        Ok(Self { path, last_hash })
+    }
+
+    // Appends `entry` to the chain. A request is never held up by the audit log,
+    // so failures are only reported.
+    pub fn record(&mut self, entry: AuditEntry) {
+        if let Err(e) = self.append(entry) {
+            eprintln!("[ERROR] Failed to write to the audit log: {:#}", e);
+        }
+    }
+
+    fn append(&mut self, mut entry: AuditEntry) -> Result<()> {
+        entry.prev_hash = self.last_hash.clone();
+        let record = AuditRecord { hash: entry.hash()?, entry };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        let mut options = fs::OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path)
+            .with_context(|| format!("Failed to open {:?}", self.path))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write {:?}", self.path))?;
+        self.last_hash = record.hash;
+        Ok(())
+    }
+}
+
+// Every entry in the data directory's audit log, oldest first
+pub fn read(data_dir: &Path) -> Result<Vec<AuditRecord>> {
+    read_records(&data_dir.join(AUDIT_FILE))
+}
+
+fn read_records(path: &Path) -> Result<Vec<AuditRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).with_context(|| format!("Failed to parse line {} of {:?}", index + 1, path))
+        })
+        .collect()
+}
+
+// Checks that every entry is unmodified and follows the one before it. The error names
+// the first entry where the chain breaks, counting from 1.
+pub fn verify(records: &[AuditRecord]) -> Result<()> {
+    let mut prev_hash = GENESIS_HASH;
+    for (index, record) in records.iter().enumerate() {
+        if record.entry.prev_hash != prev_hash {
+            return Err(anyhow::anyhow!("Entry {} doesn't follow the entry before it; entries were removed or reordered", index + 1));
+        }
+        if record.entry.hash()? != record.hash {
+            return Err(anyhow::anyhow!("Entry {} was modified after it was written", index + 1));
+        }
+        prev_hash = &record.hash;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity;
+
+    // Three entries, the last one written after reopening the log
+    fn write_log(data_dir: &Path) -> Vec<AuditRecord> {
+        let peer = PeerId::from(identity::Keypair::generate_ed25519().public());
+        let mut log = AuditLog::open(data_dir).unwrap();
+        log.record(AuditEntry::new(&peer, "1", Action::Pair, Decision::Accepted));
+        log.record(AuditEntry::new(&peer, "2", Action::ListRepos, Decision::Accepted));
+        let mut log = AuditLog::open(data_dir).unwrap();
+        log.record(AuditEntry::new(&peer, "3", Action::Commit, Decision::Rejected));
+        read(data_dir).unwrap()
+    }
+
+    fn verify_error(records: &[AuditRecord]) -> String {
+        verify(records).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn untouched_log_verifies() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let records = write_log(data_dir.path());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].entry.prev_hash, GENESIS_HASH);
+        verify(&records).unwrap();
+        verify(&[]).unwrap();
+    }
+
+    #[test]
+    fn modified_entry_is_reported() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut records = write_log(data_dir.path());
+        records[1].entry.decision = Decision::Rejected;
+        assert_eq!(verify_error(&records), "Entry 2 was modified after it was written");
+
+        // Fixing up the entry's own hash still breaks the link from the next one
+        records[1].hash = records[1].entry.hash().unwrap();
+        assert!(verify_error(&records).starts_with("Entry 3 doesn't follow"));
+    }
+
+    #[test]
+    fn removed_entry_is_reported() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut records = write_log(data_dir.path());
+        records.remove(1);
+        assert!(verify_error(&records).starts_with("Entry 2 doesn't follow"));
+        records.remove(0);
+        assert!(verify_error(&records).starts_with("Entry 1 doesn't follow"));
+    }
+
+    #[test]
+    fn reordered_entries_are_reported() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut records = write_log(data_dir.path());
+        records.swap(1, 2);
+        assert!(verify_error(&records).starts_with("Entry 2 doesn't follow"));
+    }
+}
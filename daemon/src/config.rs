@@ -0,0 +1,380 @@
+// Daemon settings, read from the TOML file given with `--config`. Every key is optional,
+// and command line flags override the file. Relative paths are relative to the directory
+// the daemon runs in.
+//
+//   data_dir = "/var/lib/remote-commit"
+//   trusted_peers = "/etc/remote-commit/trusted_peers.json"
+//   repositories = "/etc/remote-commit/repositories.json"
+//   in_memory = true
+//
+//   [network]
+//   listen_addresses = ["/ip4/0.0.0.0/tcp/4001", "/ip4/0.0.0.0/udp/4001/quic-v1"]
+//   quic = true
+//   private = true
+//   bootstrap_nodes = ["/ip4/192.168.1.5/tcp/4001/p2p/12D3KooW..."]
+//   pre_shared_key = "<64 hex characters from `daemon generate-psk`>"
+//   relays = ["/ip4/203.0.113.7/tcp/4001/p2p/12D3KooW..."]
+//   identify_protocol = "/emergency-git/1.0"
+//
+//   [logging]
+//   level = "warn"
+use anyhow::{Context, Result};
+use libp2p::{multiaddr::Protocol, pnet::PreSharedKey, Multiaddr};
+use protocol::{parse_pre_shared_key, PUBLIC_BOOTSTRAP_NODES};
+use serde::{de::Error as _, Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Where the daemon keeps its keypair, pairing codes and audit log
+    pub data_dir: PathBuf,
+    pub trusted_peers: PathBuf,
+    pub repositories: PathBuf,
+    // Commit without writing to working trees, see `--in-memory`
+    pub in_memory: bool,
+    pub network: NetworkConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    // `None` listens on a random TCP port, and a random UDP port for QUIC
+    #[serde(deserialize_with = "optional_multiaddrs")]
+    pub listen_addresses: Option<Vec<Multiaddr>>,
+    // Accept and dial QUIC connections besides TCP. `None` uses QUIC unless there is a
+    // pre-shared key, which QUIC can't carry.
+    pub quic: Option<bool>,
+    // Stays off the public IPFS DHT: Kademlia runs under our own protocol name, and
+    // without bootstrap nodes peers are only found through mDNS and the addresses they dial.
+    pub private: bool,
+    // Kademlia peers to join the DHT through. Each must end in `/p2p/<peer id>`. `None`
+    // uses the public IPFS nodes, or none at all in private mode.
+    #[serde(deserialize_with = "optional_multiaddrs")]
+    pub bootstrap_nodes: Option<Vec<Multiaddr>>,
+    // Key of the private network (pnet). Peers without it can't even open a connection.
+    #[serde(deserialize_with = "optional_pre_shared_key")]
+    pub pre_shared_key: Option<PreSharedKey>,
+    // Circuit relays to reserve a slot on, so clients can reach a daemon behind NAT. Each
+    // must end in `/p2p/<peer id>`, and on a private network the relay needs the key too.
+    #[serde(deserialize_with = "multiaddrs")]
+    pub relays: Vec<Multiaddr>,
+    // Protocol version the daemon announces through identify
+    pub identify_protocol: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    // Level of libp2p's own logs. `RUST_LOG` takes precedence when it is set.
+    pub level: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("."),
+            trusted_peers: PathBuf::from("trusted_peers.json"),
+            repositories: PathBuf::from("repositories.json"),
+            in_memory: false,
+            network: NetworkConfig::default(),
+            logging: LoggingConfig::default(),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            listen_addresses: None,
+            quic: None,
+            private: false,
+            bootstrap_nodes: None,
+            pre_shared_key: None,
+            relays: Vec::new(),
+            identify_protocol: "/emergency-git/1.0".to_string(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn quic(&self) -> bool {
+        self.quic.unwrap_or(self.pre_shared_key.is_none())
+    }
+
+    pub fn listen_addresses(&self) -> Vec<Multiaddr> {
+        if let Some(addresses) = &self.listen_addresses {
+            return addresses.clone();
+        }
+        let mut addresses = vec!["/ip4/0.0.0.0/tcp/0".parse().expect("valid default listen address")];
+        if self.quic() {
+            addresses.push("/ip4/0.0.0.0/udp/0/quic-v1".parse().expect("valid default listen address"));
+        }
+        addresses
+    }
+
+    pub fn bootstrap_nodes(&self) -> Vec<Multiaddr> {
+        match &self.bootstrap_nodes {
+            Some(nodes) => nodes.clone(),
+            None if self.private => Vec::new(),
+            None => PUBLIC_BOOTSTRAP_NODES
+                .iter()
+                .map(|address| address.parse().expect("valid public bootstrap node"))
+                .collect(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self { level: "warn".to_string() }
+    }
+}
+
+impl Config {
+    // The defaults when `path` is `None`
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        let file_content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {:?}", path))?;
+        toml::from_str(&file_content).with_context(|| format!("Invalid config file {:?}", path))
+    }
+
+    // Applies the command line flags that override config keys. Whatever is left belongs
+    // to a subcommand, and main rejects anything nobody asked for.
+    pub fn override_from_args(&mut self, args: &mut pico_args::Arguments) -> Result<()> {
+        if let Some(data_dir) = args.opt_value_from_str("--data-dir")? {
+            self.data_dir = data_dir;
+        }
+        if let Some(trusted_peers) = args.opt_value_from_str("--trusted-peers")? {
+            self.trusted_peers = trusted_peers;
+        }
+        if let Some(repositories) = args.opt_value_from_str("--repositories")? {
+            self.repositories = repositories;
+        }
+        if args.contains("--in-memory") {
+            self.in_memory = true;
+        }
+        // Repeat `--listen` to listen on several addresses
+        let listen_addresses: Vec<Multiaddr> = args.values_from_str("--listen")?;
+        if !listen_addresses.is_empty() {
+            self.network.listen_addresses = Some(listen_addresses);
+        }
+        if args.contains("--no-quic") {
+            self.network.quic = Some(false);
+        }
+        if args.contains("--private") {
+            self.network.private = true;
+        }
+        let bootstrap_nodes: Vec<Multiaddr> = args.values_from_str("--bootstrap")?;
+        if !bootstrap_nodes.is_empty() {
+            self.network.bootstrap_nodes = Some(bootstrap_nodes);
+        }
+        let relays: Vec<Multiaddr> = args.values_from_str("--relay")?;
+        if !relays.is_empty() {
+            self.network.relays = relays;
+        }
+        if let Some(identify_protocol) = args.opt_value_from_str("--identify-protocol")? {
+            self.network.identify_protocol = identify_protocol;
+        }
+        if let Some(level) = args.opt_value_from_str("--log-level")? {
+            self.logging.level = level;
+        }
+        Ok(())
+    }
+
+    // Catches settings that parse but can't work, after flags have been applied
+    pub fn validate(&self) -> Result<()> {
+        let listen_addresses = self.network.listen_addresses();
+        if listen_addresses.is_empty() {
+            return Err(anyhow::anyhow!("network.listen_addresses: the daemon needs at least one address to listen on"));
+        }
+        if self.network.quic == Some(true) && self.network.pre_shared_key.is_some() {
+            return Err(anyhow::anyhow!(
+                "network.quic: QUIC connections can't use network.pre_shared_key, so QUIC has to stay off on a pre-shared key network"
+            ));
+        }
+        if !self.network.quic() {
+            if let Some(address) = listen_addresses.iter().find(|address| is_quic(address)) {
+                return Err(anyhow::anyhow!("network.listen_addresses: {} is a QUIC address, but QUIC is off", address));
+            }
+        }
+        for address in self.network.bootstrap_nodes.iter().flatten() {
+            if !matches!(address.iter().last(), Some(Protocol::P2p(_))) {
+                return Err(anyhow::anyhow!("network.bootstrap_nodes: {} must end in /p2p/<peer id>", address));
+            }
+        }
+        for address in &self.network.relays {
+            if !matches!(address.iter().last(), Some(Protocol::P2p(_))) {
+                return Err(anyhow::anyhow!("network.relays: {} must end in /p2p/<peer id>", address));
+            }
+        }
+        if self.network.pre_shared_key.is_some() && !self.network.private {
+            return Err(anyhow::anyhow!(
+                "network.pre_shared_key needs network.private = true, since peers without the key, such as the public bootstrap nodes, can't connect"
+            ));
+        }
+        if !self.network.identify_protocol.starts_with('/') {
+            return Err(anyhow::anyhow!(
+                "network.identify_protocol: {:?} must start with /, e.g. \"/emergency-git/1.0\"",
+                self.network.identify_protocol
+            ));
+        }
+        if !LOG_LEVELS.contains(&self.logging.level.as_str()) {
+            return Err(anyhow::anyhow!(
+                "logging.level: {:?} is not one of {}",
+                self.logging.level,
+                LOG_LEVELS.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn is_quic(address: &Multiaddr) -> bool {
+    address.iter().any(|protocol| matches!(protocol, Protocol::QuicV1 | Protocol::Quic))
+}
+
+// Multiaddrs are written as strings, and a bad one is reported with the key it is under
+fn multiaddrs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Multiaddr>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|address| address.parse().map_err(|e| D::Error::custom(format!("invalid multiaddr {}: {}", address, e))))
+        .collect()
+}
+
+fn optional_pre_shared_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PreSharedKey>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    parse_pre_shared_key(&hex)
+        .map(Some)
+        .map_err(|_| D::Error::custom("the pre-shared key must be 64 hex characters"))
+}
+
+fn optional_multiaddrs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Multiaddr>>, D::Error> {
+    multiaddrs(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEER_ID: &str = "12D3KooWQVBZXQ9hCspZVKC1oa3Zartr8SXZtfidL3GDLuL8x8nL";
+
+    fn parse(toml: &str) -> Result<Config> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    fn validate(toml: &str) -> Result<Config> {
+        let config = parse(toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn error_of(toml: &str) -> String {
+        format!("{:#}", validate(toml).unwrap_err())
+    }
+
+    fn pre_shared_key() -> String {
+        "ab".repeat(32)
+    }
+
+    #[test]
+    fn empty_file_uses_the_defaults() {
+        let config = validate("").unwrap();
+        assert_eq!(config.data_dir, PathBuf::from("."));
+        assert!(config.network.quic());
+        assert_eq!(config.network.listen_addresses().len(), 2);
+        assert!(!config.network.bootstrap_nodes().is_empty());
+        assert_eq!(config.logging.level, "warn");
+    }
+
+    #[test]
+    fn pre_shared_key_turns_quic_off() {
+        let config = validate(&format!("[network]\nprivate = true\npre_shared_key = \"{}\"", pre_shared_key())).unwrap();
+        assert!(!config.network.quic());
+        assert!(config.network.listen_addresses().iter().all(|address| !is_quic(address)));
+        assert!(config.network.bootstrap_nodes().is_empty());
+    }
+
+    #[test]
+    fn quic_with_a_pre_shared_key_is_rejected() {
+        let toml = format!("[network]\nprivate = true\nquic = true\npre_shared_key = \"{}\"", pre_shared_key());
+        assert!(error_of(&toml).contains("network.quic"));
+    }
+
+    #[test]
+    fn pre_shared_key_needs_private() {
+        let toml = format!("[network]\npre_shared_key = \"{}\"", pre_shared_key());
+        assert!(error_of(&toml).contains("needs network.private = true"));
+    }
+
+    #[test]
+    fn malformed_pre_shared_key_is_rejected() {
+        assert!(parse("[network]\nprivate = true\npre_shared_key = \"abc\"").is_err());
+    }
+
+    #[test]
+    fn bootstrap_nodes_and_relays_need_a_peer_id() {
+        assert!(error_of("[network]\nbootstrap_nodes = [\"/ip4/192.0.2.1/tcp/4001\"]").contains("network.bootstrap_nodes"));
+        assert!(error_of("[network]\nrelays = [\"/ip4/192.0.2.1/tcp/4001\"]").contains("network.relays"));
+        let config = validate(&format!(
+            "[network]\nbootstrap_nodes = [\"/ip4/192.0.2.1/tcp/4001/p2p/{0}\"]\nrelays = [\"/ip4/192.0.2.1/tcp/4001/p2p/{0}\"]",
+            PEER_ID
+        ))
+        .unwrap();
+        assert_eq!(config.network.bootstrap_nodes().len(), 1);
+        assert_eq!(config.network.relays.len(), 1);
+    }
+
+    #[test]
+    fn quic_listen_address_needs_quic() {
+        let toml = "[network]\nquic = false\nlisten_addresses = [\"/ip4/0.0.0.0/tcp/4001\", \"/ip4/0.0.0.0/udp/4001/quic-v1\"]";
+        assert!(error_of(toml).contains("is a QUIC address, but QUIC is off"));
+        assert!(error_of("[network]\nlisten_addresses = []").contains("at least one address"));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse("data_directory = \"/tmp\"").is_err());
+        assert!(parse("[network]\nprivte = true").is_err());
+        assert!(parse("[logging]\nlevel = \"info\"\nformat = \"json\"").is_err());
+    }
+
+    #[test]
+    fn log_level_must_be_known() {
+        assert_eq!(validate("[logging]\nlevel = \"debug\"").unwrap().logging.level, "debug");
+        assert!(error_of("[logging]\nlevel = \"verbose\"").contains("logging.level"));
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let mut config = parse(&format!(
+            "data_dir = \"/var/lib/remote-commit\"\n[network]\nrelays = [\"/ip4/192.0.2.1/tcp/4001/p2p/{}\"]\n[logging]\nlevel = \"info\"",
+            PEER_ID
+        ))
+        .unwrap();
+        let mut args = pico_args::Arguments::from_vec(
+            ["--data-dir", "/tmp/daemon", "--no-quic", "--listen", "/ip4/127.0.0.1/tcp/4001", "--listen", "/ip4/127.0.0.1/tcp/4002", "--log-level", "trace", "pair-code"]
+                .iter()
+                .map(Into::into)
+                .collect(),
+        );
+        config.override_from_args(&mut args).unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(config.data_dir, PathBuf::from("/tmp/daemon"));
+        assert!(!config.network.quic());
+        assert_eq!(config.network.listen_addresses().len(), 2);
+        assert_eq!(config.network.relays.len(), 1);
+        assert_eq!(config.logging.level, "trace");
+        // The subcommand is left for main
+        assert_eq!(args.finish(), vec![std::ffi::OsString::from("pair-code")]);
+    }
+}
@@ -0,0 +1,62 @@
+// The JSON files the running daemon shares with its subcommands, such as
+// pairing_codes.json, which `daemon pair-code` writes while the daemon redeems from it.
+// A change is a read-modify-write under a lock, and the new contents replace the file in
+// one rename, so a reader never sees a half-written file.
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Held while a process reads, changes and writes back a shared file. Released on drop.
+pub struct FileLock {
+    _file: File,
+}
+
+// Waits until no other process is changing `path`. The lock is taken on `<path>.lock`,
+// since `path` itself is replaced on every write.
+pub fn lock(path: &Path) -> Result<FileLock> {
+    let lock_path = sibling(path, "lock");
+    let file = private_options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {:?}", lock_path))?;
+    file.lock().with_context(|| format!("Failed to lock {:?}", lock_path))?;
+    Ok(FileLock { _file: file })
+}
+
+// Replaces `path` with `contents` through a temporary file, readable only by the daemon's user
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp_path = sibling(path, "tmp");
+    // Left behind by a crash, possibly with other permissions
+    let _ = fs::remove_file(&tmp_path);
+    let mut file = private_options()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)
+        .with_context(|| format!("Failed to create {:?}", tmp_path))?;
+    file.write_all(contents)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {:?}", path))
+}
+
+fn private_options() -> fs::OpenOptions {
+    let mut options = fs::OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+// `path` with `.extension` appended to its file name
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
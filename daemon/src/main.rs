@@ -1,76 +1,74 @@
-use anyhow::{anyhow, Result};
+use anyhow::{Context, Result};
 use libp2p::{
-    gossipsub, mdns,
-    swarm::{SwarmEvent, SwarmBuilder},
-    identity,
+    pnet::PreSharedKey,
+    mdns,
+    swarm::{behaviour::toggle::Toggle, SwarmEvent, SwarmBuilder},
     PeerId,
-    Swarm,
     // Import NetworkBehaviour from the correct location
     swarm::NetworkBehaviour,
-    // Import transport building utilities
-    noise,
-    tcp,
-    yamux,
-    Transport,
-    gossipsub::Message,
+    request_response::{self, ProtocolSupport, ResponseChannel},
     kad::{self, store::MemoryStore},
     identify,
     relay,
+    dcutr,
+    autonat,
+    multiaddr::Protocol,
     Multiaddr,
 };
 use futures::StreamExt; // Required for select_next_some()
 use tokio::select;
-use std::collections::HashSet;
-use std::fs;
-use std::io::{self, Write};
-use std::path::PathBuf;
-use pico_args;
-use serde_json;
-use tokio::time::{sleep, Duration};
+use tokio::sync::mpsc;
+use std::path::{Path, PathBuf};
+use std::borrow::Cow;
+use std::iter;
+use std::time::Duration;
 
-mod protocol;
-use protocol::{CommitRequest, CommitResponse, NetworkMessage};
-
-// --- NEW: A struct to manage our trusted peers ---
-struct PeerManager {
-    trusted_peers_path: PathBuf,
-    trusted_peers: HashSet<PeerId>,
-}
+use protocol::{
+    Capability, CommitRequest, CommitResponse, Envelope, ErrorKind, Handshake, NetworkMessage, PushResult,
+    RemoteCommitCodec, RemoteCommitProtocol, KADEMLIA_PROTOCOL, PROTOCOL_VERSION,
+};
+use protocol::pre_shared_key_hex;
+use p2p_node::{build_transport, keystore};
+mod repos;
+use repos::{RepoRegistry, RepoSettings};
+mod files;
+mod pairing;
+use pairing::{unix_now, PairingCodes};
+mod peers;
+use peers::{AuthorIdentity, PeerManager, TrustedPeer};
+mod policy;
+use policy::PeerPolicy;
+mod audit;
+use audit::{Action, AuditEntry, AuditLog, Decision};
+mod config;
+use config::Config;
+use protocol::PairingUri;
+use qrcode::{render::unicode, QrCode};
 
-impl PeerManager {
-    fn new() -> anyhow::Result<Self> {
-        let path = PathBuf::from("trusted_peers.json");
-        let peers = if path.exists() {
-            let file_content = fs::read_to_string(&path)?;
-            serde_json::from_str(&file_content)?
-        } else {
-            HashSet::new()
-        };
-        println!("Loaded {} trusted peers.", peers.len());
-        Ok(Self { trusted_peers_path: path, trusted_peers: peers })
-    }
+const KEYPAIR_FILE: &str = "daemon_identity.key";
 
-    fn is_trusted(&self, peer_id: &PeerId) -> bool {
-        self.trusted_peers.contains(peer_id)
-    }
-
-    fn add_trusted_peer(&mut self, peer_id: PeerId) -> anyhow::Result<()> {
-        self.trusted_peers.insert(peer_id);
-        let json = serde_json::to_string_pretty(&self.trusted_peers)?;
-        fs::write(&self.trusted_peers_path, json)?;
-        println!("Added new trusted peer: {}. Total: {}", peer_id, self.trusted_peers.len());
-        Ok(())
-    }
-}
+// What this daemon tells clients it can do in the handshake
+const CAPABILITIES: &[Capability] = &[
+    Capability::MultiFile,
+    Capability::Branches,
+    Capability::Push,
+    Capability::ListRepos,
+];
 
 // This derive macro will now work correctly with proper imports
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "DaemonBehaviourEvent")]
 struct DaemonBehaviour {
-    gossipsub: gossipsub::Behaviour,
-    mdns: mdns::tokio::Behaviour,
+    request_response: request_response::Behaviour<RemoteCommitCodec>,
+    // Off when mDNS couldn't start, see below
+    mdns: Toggle<mdns::tokio::Behaviour>,
     identify: identify::Behaviour,
-    relay: relay::Behaviour,
+    // Reservations on the configured relays, so peers behind other NATs can still reach us
+    relay_client: relay::client::Behaviour,
+    // Upgrades relayed connections to direct ones by hole punching
+    dcutr: dcutr::Behaviour,
+    // Finds out whether we are reachable from outside, by asking peers to dial us back
+    autonat: autonat::Behaviour,
     kademlia: kad::Kademlia<MemoryStore>,
 }
 
@@ -78,122 +76,243 @@ struct DaemonBehaviour {
 async fn main() -> Result<()> {
     // --- NEW: Parse command-line arguments ---
     let mut args = pico_args::Arguments::from_env();
-    let is_pairing_mode = args.contains("--pair");
+    // Settings come from the config file, and flags override individual keys
+    let config_path: Option<PathBuf> = args.opt_value_from_str("--config")?;
+    let mut config = Config::load(config_path.as_deref())?;
+    config.override_from_args(&mut args)?;
+    // Taken once the daemon's own flags are out of the way, so they can come before it too
+    let subcommand = args.subcommand()?;
+    config.validate().context("Invalid configuration")?;
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(&config.logging.level)).init();
+    // Where the daemon keeps its keypair, so its PeerId (and clients' saved addresses) survive restarts
+    let data_dir = config.data_dir.clone();
+    // Commit without writing to the working tree, so local edits on this machine are never clobbered
+    let in_memory = config.in_memory;
+    match subcommand.as_deref() {
+        None => {}
+        Some("pair-code") => return issue_pairing_code(args, &data_dir, config.network.pre_shared_key),
+        Some("generate-psk") => {
+            finish_args(args)?;
+            // For network.pre_shared_key in the config
+            println!("{}", pre_shared_key_hex(&PreSharedKey::new(rand::random())));
+            return Ok(());
+        }
+        Some("peers") => return manage_peers(args, &config.trusted_peers),
+        Some("audit") => return query_audit_log(args, &data_dir),
+        Some(other) => return Err(anyhow::anyhow!("Unknown command: {}", other)),
+    }
+    finish_args(args)?;
 
-    let mut peer_manager = PeerManager::new()?;
-    let id_keys = identity::Keypair::generate_ed25519();
+    let mut peer_manager = PeerManager::new(&config.trusted_peers)?;
+    let repo_registry = RepoRegistry::load(&config.repositories)?;
+    let pairing_codes = PairingCodes::new(&data_dir);
+    let mut audit_log = AuditLog::open(&data_dir)?;
+    let id_keys = keystore::get_or_create_identity(&data_dir, KEYPAIR_FILE)?;
     let local_peer_id = PeerId::from(id_keys.public());
     println!("------------------------------------------------------");
     println!("Daemon Peer ID: {}", local_peer_id);
-    if is_pairing_mode {
-        println!("DAEMON IS IN PAIRING MODE.");
-        println!("Client can now send a pair request.");
-    } else {
-        println!("DAEMON IS IN STANDARD MODE.");
-        println!("Run with --pair to allow new clients to connect.");
+    if let Some(key) = &config.network.pre_shared_key {
+        // Lets operators check two machines share a key without showing it
+        println!("Private network key fingerprint: {}", key.fingerprint());
     }
+    println!("Run `daemon pair-code` to let a new client pair.");
     println!("------------------------------------------------------");
 
-    // Build transport manually since development_transport might not be available
-    // with your current feature set
-    let transport = tcp::tokio::Transport::default()
-        .upgrade(libp2p::core::upgrade::Version::V1)
-        .authenticate(noise::Config::new(&id_keys)?)
-        .multiplex(yamux::Config::default())
-        .boxed();
-
-    let topic = gossipsub::IdentTopic::new("emergency-git-commits");
+    let (relay_transport, relay_client) = relay::client::new(local_peer_id);
+    let transport = build_transport(&id_keys, Some(relay_transport), config.network.pre_shared_key, config.network.quic())?;
 
     // Use the modern SwarmBuilder API
     let mut swarm = {
         // --- Kademlia Setup ---
         let store = MemoryStore::new(local_peer_id);
-        let mut kademlia = kad::Kademlia::new(local_peer_id, store);
-        let bootstrap_nodes = [
-            "/ip4/104.131.131.82/tcp/4001/p2p/QmaCpDMGvV2BGHeYERUEnRQAwe3N8SzbUtfsmvsqQLuvuJ",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmQCU2EcMqAqQPR2i9bChDtGNJchTbq5TbXJJ16u19uLTa",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmcZf59bWwK5XFi76CZX8cbJ4BhTzzA3gU1ZjYZcYW3dwt"
-        ];
-        for addr in bootstrap_nodes {
-            let multiaddr: Multiaddr = addr.parse().expect("Failed to parse bootstrap address");
-            if let Some(libp2p::multiaddr::Protocol::P2p(hash)) = multiaddr.iter().last() {
-                let peer_id = PeerId::from_multihash(hash).expect("Valid PeerId multihash");
-                kademlia.add_address(&peer_id, multiaddr);
-            } else {
-                eprintln!("Could not extract PeerId from bootstrap address: {}", addr);
+        let mut kademlia_config = kad::KademliaConfig::default();
+        if config.network.private {
+            kademlia_config.set_protocol_names(vec![Cow::Borrowed(KADEMLIA_PROTOCOL)]);
+        }
+        let mut kademlia = kad::Kademlia::with_config(local_peer_id, store, kademlia_config);
+        for multiaddr in config.network.bootstrap_nodes() {
+            match multiaddr.iter().last() {
+                Some(libp2p::multiaddr::Protocol::P2p(hash)) => match PeerId::from_multihash(hash) {
+                    Ok(peer_id) => {
+                        kademlia.add_address(&peer_id, multiaddr);
+                    }
+                    Err(_) => eprintln!("[WARN] Invalid PeerId in bootstrap address: {}", multiaddr),
+                },
+                _ => eprintln!("Could not extract PeerId from bootstrap address: {}", multiaddr),
             }
         }
-        kademlia.bootstrap().unwrap();
+        // With no bootstrap nodes (or no network) there is nothing to bootstrap from yet.
+        // Peers found through mDNS are added to the DHT as they show up.
+        if let Err(e) = kademlia.bootstrap() {
+            println!("[INFO] Not bootstrapping the DHT: {}", e);
+        }
         // --- End Kademlia Setup ---
+        // The relays run an AutoNAT server too, so there is always someone to ask
+        let mut autonat = autonat::Behaviour::new(local_peer_id, Default::default());
+        for relay_address in &config.network.relays {
+            if let Some(Protocol::P2p(hash)) = relay_address.iter().last() {
+                if let Ok(peer_id) = PeerId::from_multihash(hash) {
+                    autonat.add_server(peer_id, Some(relay_address.clone()));
+                }
+            }
+        }
+        // Without multicast, e.g. in some containers, peers can still dial the daemon's
+        // addresses; they just aren't discovered on the local network
+        let mdns = match mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id) {
+            Ok(mdns) => Some(mdns),
+            Err(e) => {
+                eprintln!("[WARN] Failed to start mDNS, local peers won't be discovered: {}", e);
+                None
+            }
+        };
         let behaviour = DaemonBehaviour {
-            gossipsub: {
-                let gossipsub_config = gossipsub::Config::default();
-                gossipsub::Behaviour::new(
-                    gossipsub::MessageAuthenticity::Signed(id_keys.clone()),
-                    gossipsub_config,
-                )
-                .map_err(|e| anyhow!(e))?
-            },
-            mdns: mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)?,
+            request_response: request_response::Behaviour::new(
+                RemoteCommitCodec,
+                iter::once((RemoteCommitProtocol, ProtocolSupport::Inbound)),
+                Default::default(),
+            ),
+            mdns: mdns.into(),
             identify: identify::Behaviour::new(identify::Config::new(
-                "/emergency-git/1.0".into(),
+                config.network.identify_protocol.clone(),
                 id_keys.public(),
             )),
-            relay: relay::Behaviour::new(local_peer_id, Default::default()),
+            relay_client,
+            dcutr: dcutr::Behaviour::new(local_peer_id),
+            autonat,
             kademlia,
         };
         SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id).build()
     };
 
-    // Subscribe to the topic AFTER the swarm is created
-    swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
+    for address in &config.network.listen_addresses() {
+        swarm.listen_on(address.clone())
+            .with_context(|| format!("Failed to listen on {}", address))?;
+    }
+    // Listening on a circuit address reserves a slot on the relay. The relayed address
+    // then shows up as a listen address, and ends up in pairing URIs like the others.
+    for relay_address in &config.network.relays {
+        let circuit_address = relay_address.clone().with(Protocol::P2pCircuit);
+        swarm.listen_on(circuit_address.clone())
+            .with_context(|| format!("Failed to listen on {}", circuit_address))?;
+    }
+    let mut listen_addresses: Vec<Multiaddr> = Vec::new();
+    let (finished_commits_tx, mut finished_commits) = mpsc::unbounded_channel::<FinishedCommit>();
 
-    swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+    // The addresses in the data directory are only good while the daemon runs
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
 
     println!("Starting P2P daemon event loop...");
     loop {
         select! {
+            result = &mut shutdown => {
+                result?;
+                println!("Shutting down...");
+                return pairing::remove_listen_addresses(&data_dir);
+            }
+            Some(finished) = finished_commits.recv() => {
+                audit_log.record(finished.audit_entry.with_response(&finished.response));
+                send_reply(finished.channel, finished.request_id, NetworkMessage::Response(finished.response), &mut swarm.behaviour_mut().request_response);
+            }
             event = swarm.select_next_some() => match event {
                 SwarmEvent::NewListenAddr { address, .. } => {
                     println!("Daemon listening on {}/p2p/{}", address, local_peer_id);
+                    listen_addresses.push(address);
+                    if let Err(e) = pairing::save_listen_addresses(&data_dir, &listen_addresses) {
+                        eprintln!("[WARN] Failed to record listen addresses: {:#}", e);
+                    }
+                }
+                SwarmEvent::ExpiredListenAddr { address, .. } => {
+                    listen_addresses.retain(|listen_address| *listen_address != address);
+                    if let Err(e) = pairing::save_listen_addresses(&data_dir, &listen_addresses) {
+                        eprintln!("[WARN] Failed to record listen addresses: {:#}", e);
+                    }
                 }
 
                 SwarmEvent::Behaviour(DaemonBehaviourEvent::Mdns(mdns::Event::Discovered(list))) => {
-                    for (peer_id, _multiaddr) in list {
+                    for (peer_id, multiaddr) in list {
                         println!("mDNS discovered a new peer: {}", peer_id);
-                        swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                        swarm.behaviour_mut().kademlia.add_address(&peer_id, multiaddr);
                     }
                 },
                 
-                SwarmEvent::Behaviour(DaemonBehaviourEvent::Gossipsub(gossipsub::Event::Message {
-                    propagation_source: source_peer,
-                    message,
-                    ..
+                // Each request arrives on its own stream, and the reply goes back only to the peer that sent it
+                SwarmEvent::Behaviour(DaemonBehaviourEvent::RequestResponse(request_response::Event::Message {
+                    peer: source_peer,
+                    message: request_response::Message::Request { request, channel, .. },
                 })) => {
-                    let source_peer = match message.source {
-                        Some(peer_id) => peer_id,
-                        None => continue, // Ignore anonymous messages
-                    };
-                    match serde_json::from_slice::<NetworkMessage>(&message.data) {
-                        Ok(NetworkMessage::PairRequest) => {
-                            if is_pairing_mode {
-                                handle_pair_request(source_peer, &mut peer_manager, topic.clone(), &mut swarm.behaviour_mut().gossipsub).await;
-                            } else {
-                                println!("Ignoring pair request from {}. Daemon not in --pair mode.", source_peer);
+                    let Envelope { request_id, message, .. } = request;
+                    match message {
+                        NetworkMessage::Hello(hello) => {
+                            if hello.version != PROTOCOL_VERSION {
+                                println!("Peer {} speaks protocol version {}, this daemon speaks {}", source_peer, hello.version, PROTOCOL_VERSION);
                             }
+                            let handshake = Handshake { version: PROTOCOL_VERSION, capabilities: CAPABILITIES.to_vec() };
+                            send_reply(channel, request_id, NetworkMessage::Hello(handshake), &mut swarm.behaviour_mut().request_response);
                         }
-                        Ok(NetworkMessage::Request(request)) => {
-                            if peer_manager.is_trusted(&source_peer) {
-                                println!("Received trusted commit request from {}", source_peer);
-                                handle_commit_request(request, topic.clone(), &mut swarm.behaviour_mut().gossipsub);
-                            } else {
-                                println!("IGNORING untrusted commit request from {}", source_peer);
+                        NetworkMessage::PairRequest { code } => {
+                            let response = handle_pair_request(source_peer, &code, &pairing_codes, &mut peer_manager);
+                            let (decision, reason) = match &response {
+                                NetworkMessage::PairDenied { reason } => (Decision::Rejected, Some(reason.clone())),
+                                _ => (Decision::Accepted, None),
+                            };
+                            audit_log.record(AuditEntry { reason, ..AuditEntry::new(&source_peer, &request_id, Action::Pair, decision) });
+                            send_reply(channel, request_id, response, &mut swarm.behaviour_mut().request_response);
+                        }
+                        NetworkMessage::Request(request) => {
+                            let entry = AuditEntry::commit(&source_peer, &request_id, &request);
+                            match peer_manager.admit(&source_peer) {
+                                Ok(()) => {
+                                    println!("Received trusted commit request from {}", source_peer);
+                                    let author = peer_manager.author_of(&source_peer);
+                                    let policy = peer_manager.policy_of(&source_peer);
+                                    match check_commit_request(&request, &policy, &repo_registry) {
+                                        Ok(settings) => {
+                                            // Committing, and above all pushing, can take a while, so it
+                                            // runs off the event loop and the reply is sent once it is done
+                                            let settings = settings.clone();
+                                            let finished_commits = finished_commits_tx.clone();
+                                            tokio::task::spawn_blocking(move || {
+                                                let response = commit_changes(request, author, in_memory, &settings);
+                                                let finished = FinishedCommit { channel, request_id, audit_entry: entry, response };
+                                                // Only fails once the event loop is gone
+                                                let _ = finished_commits.send(finished);
+                                            });
+                                        }
+                                        Err(response) => {
+                                            audit_log.record(entry.with_response(&response));
+                                            send_reply(channel, request_id, NetworkMessage::Response(response), &mut swarm.behaviour_mut().request_response);
+                                        }
+                                    }
+                                }
+                                Err(reason) => {
+                                    println!("Refusing untrusted commit request from {}", source_peer);
+                                    audit_log.record(entry);
+                                    let response = rejected(ErrorKind::Forbidden, reason);
+                                    send_reply(channel, request_id, NetworkMessage::Response(response), &mut swarm.behaviour_mut().request_response);
+                                }
                             }
                         }
+                        NetworkMessage::ListRepos => {
+                            let (decision, response) = match peer_manager.admit(&source_peer) {
+                                Ok(()) => {
+                                    let policy = peer_manager.policy_of(&source_peer);
+                                    (Decision::Accepted, NetworkMessage::RepoList(repo_registry.list(&policy)))
+                                }
+                                Err(reason) => {
+                                    println!("Refusing untrusted repository list request from {}", source_peer);
+                                    (Decision::Untrusted, NetworkMessage::Denied { reason })
+                                }
+                            };
+                            audit_log.record(AuditEntry::new(&source_peer, &request_id, Action::ListRepos, decision));
+                            send_reply(channel, request_id, response, &mut swarm.behaviour_mut().request_response);
+                        }
                         _ => {}
                     }
                 }
+                SwarmEvent::Behaviour(DaemonBehaviourEvent::RequestResponse(request_response::Event::InboundFailure { peer, error, .. })) => {
+                    eprintln!("[WARN] Failed to answer request from {}: {}", peer, error);
+                }
                 SwarmEvent::Behaviour(DaemonBehaviourEvent::Identify(identify::Event::Received {
                     peer_id,
                     info,
@@ -220,89 +339,396 @@ async fn main() -> Result<()> {
                     }
                     println!("✅✅✅ --- END OF ADDRESSES --- ✅✅✅\n");
                 },
+                SwarmEvent::Behaviour(DaemonBehaviourEvent::RelayClient(relay::client::Event::ReservationReqAccepted {
+                    relay_peer_id,
+                    renewal: false,
+                    ..
+                })) => {
+                    println!("[Relay] Reserved a slot on relay {}", relay_peer_id);
+                }
+                SwarmEvent::Behaviour(DaemonBehaviourEvent::RelayClient(relay::client::Event::ReservationReqFailed {
+                    relay_peer_id,
+                    error,
+                    ..
+                })) => {
+                    eprintln!("[WARN] Failed to reserve a slot on relay {}: {:?}", relay_peer_id, error);
+                }
+                SwarmEvent::Behaviour(DaemonBehaviourEvent::Dcutr(dcutr::Event::DirectConnectionUpgradeSucceeded {
+                    remote_peer_id,
+                })) => {
+                    println!("[DCUtR] Upgraded the relayed connection to {} to a direct one", remote_peer_id);
+                }
+                SwarmEvent::Behaviour(DaemonBehaviourEvent::Dcutr(dcutr::Event::DirectConnectionUpgradeFailed {
+                    remote_peer_id,
+                    error,
+                })) => {
+                    // The relayed connection keeps working, just slower
+                    println!("[DCUtR] Hole punching to {} failed, staying on the relay: {:?}", remote_peer_id, error);
+                }
+                SwarmEvent::Behaviour(DaemonBehaviourEvent::Autonat(autonat::Event::StatusChanged { new, .. })) => {
+                    match new {
+                        autonat::NatStatus::Public(address) => println!("[AutoNAT] Reachable directly at {}", address),
+                        autonat::NatStatus::Private if config.network.relays.is_empty() => {
+                            println!("[AutoNAT] Behind a NAT. Add network.relays to the config so clients outside can reach the daemon.")
+                        }
+                        autonat::NatStatus::Private => println!("[AutoNAT] Behind a NAT, reachable through the relays"),
+                        autonat::NatStatus::Unknown => {}
+                    }
+                }
                 _ => {}
             }
         }
     }
 }
 
-// --- NEW: Handler for pairing ---
-async fn handle_pair_request(
+// Resolves on Ctrl-C, or on SIGTERM, which is how service managers stop the daemon
+async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+// Issues a one-time pairing code for the running daemon to accept
+fn issue_pairing_code(mut args: pico_args::Arguments, data_dir: &Path, pre_shared_key: Option<PreSharedKey>) -> Result<()> {
+    let minutes: u64 = args.opt_value_from_str("--expires-in")?.unwrap_or(10);
+    // A name to tell the paired peer apart by in `daemon peers list`
+    let label: Option<String> = args.opt_value_from_str("--label")?;
+    // How long the paired peer stays trusted, e.g. "90d". Forever by default.
+    let trust_for: Option<humantime::Duration> = args.opt_value_from_str("--trust-for")?;
+    // Limits on what the paired peer may do, comma separated. Everything is allowed by default.
+    let policy = PeerPolicy {
+        repos: list_arg(&mut args, "--repos")?,
+        branches: list_arg(&mut args, "--branches")?,
+        paths: list_arg(&mut args, "--paths")?,
+        push: !args.contains("--no-push"),
+    };
+    policy.validate().map_err(|e| anyhow::anyhow!(e))?;
+    let author_name: Option<String> = args.opt_value_from_str("--author-name")?;
+    let author_email: Option<String> = args.opt_value_from_str("--author-email")?;
+    finish_args(args)?;
+    let author = match (author_name, author_email) {
+        (Some(name), Some(email)) => Some(AuthorIdentity { name, email }),
+        (None, None) => None,
+        _ => return Err(anyhow::anyhow!("--author-name and --author-email must be given together")),
+    };
+
+    keystore::create_private_dir(data_dir)?;
+    let trusted_for = trust_for.map(|duration| Duration::from(duration).as_secs());
+    let issued = PairingCodes::new(data_dir).issue(Duration::from_secs(minutes * 60), label, trusted_for, author, policy)?;
+    println!("Pairing code: {}", issued.display());
+    println!("It pairs one client and expires in {} minutes.", minutes);
+
+    // The URI bundles the code with where to find the daemon, so a phone can scan it
+    let addresses = pairing::load_listen_addresses(data_dir)?;
+    if addresses.is_empty() {
+        println!("Start the daemon with the same --data-dir to also get a pairing URI and QR code.");
+        return Ok(());
+    }
+    let peer_id = PeerId::from(keystore::get_or_create_identity(data_dir, KEYPAIR_FILE)?.public());
+    let uri = PairingUri { peer_id, addresses, code: issued.display(), pre_shared_key }.to_string();
+    let qr = QrCode::new(&uri)?
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build();
+    println!("Pairing URI: {}", uri);
+    println!("{}", qr);
+    // The file outlives a daemon that crashed or was killed, and its ports change on restart
+    println!("The URI has the addresses the daemon last listened on, so it only works while that daemon runs.");
+    Ok(())
+}
+
+// A comma separated list option, e.g. `--paths "docs/**,README.md"`
+fn list_arg(args: &mut pico_args::Arguments, name: &'static str) -> Result<Option<Vec<String>>> {
+    let value: Option<String> = args.opt_value_from_str(name)?;
+    Ok(value.map(|value| value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()))
+}
+
+// Fails on arguments nothing asked for, such as a misspelled flag, instead of ignoring them
+fn finish_args(args: pico_args::Arguments) -> Result<()> {
+    let unexpected = args.finish();
+    if !unexpected.is_empty() {
+        return Err(anyhow::anyhow!("Unexpected arguments: {:?}", unexpected));
+    }
+    Ok(())
+}
+
+// `daemon peers list` and `daemon peers revoke <peer id>`
+fn manage_peers(mut args: pico_args::Arguments, trusted_peers_path: &Path) -> Result<()> {
+    let mut peer_manager = PeerManager::new(trusted_peers_path)?;
+    match args.subcommand()?.as_deref() {
+        Some("list") => {
+            finish_args(args)?;
+            let now = unix_now();
+            println!("{:<54} {:<20} {:<22} {:<22} EXPIRES", "PEER ID", "LABEL", "PAIRED", "LAST SEEN");
+            for (peer_id, peer) in peer_manager.list() {
+                let expires = match peer.expires_at {
+                    Some(_) if peer.is_expired(now) => format!("{} (expired)", format_time(peer.expires_at)),
+                    _ => format_time(peer.expires_at),
+                };
+                println!(
+                    "{:<54} {:<20} {:<22} {:<22} {}",
+                    peer_id.to_string(),
+                    peer.label.as_deref().unwrap_or("-"),
+                    format_time(Some(peer.paired_at).filter(|paired_at| *paired_at > 0)),
+                    format_time(peer_manager.last_seen(peer_id)),
+                    expires,
+                );
+            }
+            Ok(())
+        }
+        Some("revoke") => {
+            let peer_id: PeerId = args.free_from_str()?;
+            finish_args(args)?;
+            if peer_manager.revoke(&peer_id)? {
+                println!("Revoked {}. It has to pair again to make requests.", peer_id);
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("{} is not a trusted peer", peer_id))
+            }
+        }
+        _ => Err(anyhow::anyhow!("Usage: daemon peers list | daemon peers revoke <peer id>")),
+    }
+}
+
+// `daemon audit` prints the audit log, optionally filtered, and `daemon audit verify` checks its hash chain
+fn query_audit_log(mut args: pico_args::Arguments, data_dir: &Path) -> Result<()> {
+    if args.subcommand()?.as_deref() == Some("verify") {
+        finish_args(args)?;
+        let records = audit::read(data_dir)?;
+        audit::verify(&records)?;
+        println!("The audit log is intact ({} entries).", records.len());
+        return Ok(());
+    }
+    let peer: Option<PeerId> = args.opt_value_from_str("--peer")?;
+    let repo: Option<String> = args.opt_value_from_str("--repo")?;
+    let decision: Option<Decision> = args.opt_value_from_str("--decision")?;
+    // Only entries newer than this, e.g. "24h"
+    let since: Option<humantime::Duration> = args.opt_value_from_str("--since")?;
+    finish_args(args)?;
+    let since = since.map(|since| unix_now().saturating_sub(since.as_secs()));
+    let peer = peer.map(|peer| peer.to_string());
+    println!("{:<22} {:<54} {:<10} {:<12} {:<10} DETAILS", "TIME", "PEER", "ACTION", "REPO", "DECISION");
+    for record in audit::read(data_dir)? {
+        let entry = record.entry;
+        if peer.as_ref().is_some_and(|peer| *peer != entry.peer)
+            || repo.is_some() && entry.repo != repo
+            || decision.is_some_and(|decision| decision != entry.decision)
+            || since.is_some_and(|since| entry.timestamp < since)
+        {
+            continue;
+        }
+        let action = match entry.action {
+            Action::Pair => "pair",
+            Action::Commit => "commit",
+            Action::ListRepos => "list-repos",
+        };
+        let mut details: Vec<String> = entry.commit_hash.into_iter().chain(entry.reason).collect();
+        if !entry.files.is_empty() {
+            details.push(format!("files: {}", entry.files.join(", ")));
+        }
+        println!(
+            "{:<22} {:<54} {:<10} {:<12} {:<10} {}",
+            format_time(Some(entry.timestamp)),
+            entry.peer,
+            action,
+            entry.repo.as_deref().unwrap_or("-"),
+            entry.decision.to_string(),
+            details.join("; "),
+        );
+    }
+    Ok(())
+}
+
+// Unix seconds as an RFC 3339 timestamp, or "-" if there is none
+fn format_time(unix_seconds: Option<u64>) -> String {
+    match unix_seconds {
+        Some(seconds) => humantime::format_rfc3339_seconds(std::time::UNIX_EPOCH + Duration::from_secs(seconds)).to_string(),
+        None => "-".to_string(),
+    }
+}
+
+// Trusts `peer_id` if it presents a pairing code that was issued and hasn't expired
+fn handle_pair_request(
     peer_id: PeerId,
+    code: &str,
+    pairing_codes: &PairingCodes,
     peer_manager: &mut PeerManager,
-    topic: gossipsub::IdentTopic,
-    gossipsub: &mut gossipsub::Behaviour,
-) {
-    println!("Pairing request received from {}. Approve? (y/n): ", peer_id);
-    io::stdout().flush().unwrap();
+) -> NetworkMessage {
+    let issued = match pairing_codes.redeem(code) {
+        Ok(Some(issued)) => issued,
+        Ok(None) => {
+            println!("[INFO] Pairing for {} denied: unknown or expired code.", peer_id);
+            return NetworkMessage::PairDenied { reason: "The pairing code is invalid or has expired".to_string() };
+        }
+        Err(e) => {
+            eprintln!("[ERROR] Failed to check pairing code: {:#}", e);
+            return NetworkMessage::PairDenied { reason: "The daemon could not check the pairing code".to_string() };
+        }
+    };
+    let now = unix_now();
+    let peer = TrustedPeer {
+        label: issued.label,
+        paired_at: now,
+        expires_at: issued.trusted_for.map(|seconds| now + seconds),
+        author: issued.author,
+        policy: issued.policy,
+    };
+    if let Err(e) = peer_manager.add_trusted_peer(peer_id, peer) {
+        eprintln!("[ERROR] Failed to save trusted peer: {}", e);
+        return NetworkMessage::PairDenied { reason: "The daemon could not save the pairing".to_string() };
+    }
+    NetworkMessage::PairSuccess
+}
 
-    let approved = tokio::task::spawn_blocking(|| {
-        let mut line = String::new();
-        io::stdin().read_line(&mut line).is_ok() && line.trim().eq_ignore_ascii_case("y")
-    }).await.unwrap_or(false);
+// A commit that ran off the event loop, waiting for its reply to be sent
+struct FinishedCommit {
+    channel: ResponseChannel<Envelope>,
+    request_id: String,
+    audit_entry: AuditEntry,
+    response: CommitResponse,
+}
 
-    if approved {
-        if let Err(e) = peer_manager.add_trusted_peer(peer_id) {
-            eprintln!("[ERROR] Failed to save trusted peer: {}", e);
-            return;
+// Decides whether a commit request may go ahead, before anything touches git. Returns the
+// settings of the repository to commit to, or the response rejecting the request.
+fn check_commit_request<'a>(
+    request: &CommitRequest,
+    policy: &PeerPolicy,
+    repo_registry: &'a RepoRegistry,
+) -> Result<&'a RepoSettings, CommitResponse> {
+    // Only repositories registered in repositories.json can be written to
+    let settings = match repo_registry.get(&request.repo) {
+        Some(settings) => settings,
+        None => {
+            println!("Rejecting commit to unregistered repository: {}", request.repo);
+            return Err(rejected(ErrorKind::Forbidden, format!("Repository {} is not registered", request.repo)));
         }
+    };
+    // Branch rules apply to the branch the commit really lands on, even when the client didn't name one
+    let branch = match request.branch.clone().or_else(|| settings.default_branch.clone()) {
+        Some(branch) => Some(branch),
+        None if policy.branches.is_some() => match settings.path_str().and_then(git_actor::head_branch) {
+            Ok(branch) => branch,
+            Err(e) => return Err(rejected(ErrorKind::Failed, format!("{:#}", e))),
+        },
+        None => None,
+    };
+    if let Err(reason) = policy.check_commit(request, branch.as_deref()) {
+        println!("Rejecting commit forbidden by the peer's policy: {}", reason);
+        return Err(rejected(ErrorKind::Forbidden, reason));
+    }
+    Ok(settings)
+}
 
-        let response = NetworkMessage::PairSuccess;
-        if let Ok(json) = serde_json::to_string(&response) {
-            let max_retries = 5;
-            for i in 0..max_retries {
-                match gossipsub.publish(topic.clone(), json.as_bytes()) {
-                    Ok(_) => {
-                        println!("[INFO] Published PairSuccess response.");
-                        return;
-                    }
-                    Err(e) if i < max_retries - 1 => {
-                        eprintln!("[WARN] Failed to publish reply (attempt {}): {}. Retrying...", i + 1, e);
-                        sleep(Duration::from_millis(500)).await;
-                    }
-                    Err(e) => {
-                        eprintln!("[ERROR] Failed to publish pairing success message after all retries: {:?}", e);
-                        return;
-                    }
-                }
-            }
-        }
+// A response for a request that was refused before anything was committed
+fn rejected(error_kind: ErrorKind, message: String) -> CommitResponse {
+    CommitResponse {
+        success: false,
+        commit_hash: None,
+        error_message: Some(message),
+        error_kind: Some(error_kind),
+        push_result: None,
+    }
+}
+
+// Answers a request on the stream it came in on, echoing the client's request id
+fn send_reply(
+    channel: ResponseChannel<Envelope>,
+    request_id: String,
+    message: NetworkMessage,
+    request_response: &mut request_response::Behaviour<RemoteCommitCodec>,
+) {
+    if request_response.send_response(channel, Envelope::new(request_id, message)).is_err() {
+        eprintln!("Failed to send response: the client has disconnected.");
     } else {
-        println!("[INFO] Pairing for {} denied.", peer_id);
+        println!("Sent response.");
     }
 }
 
-// --- MODIFIED: Handler for commits ---
-fn handle_commit_request(request: CommitRequest, topic: gossipsub::IdentTopic, gossipsub: &mut gossipsub::Behaviour) {
-    let response = match git_actor::perform_commit(
-        &request.repo_path,
-        &request.file_path,
-        &request.new_content,
-        &request.commit_message,
-    ) {
+// Runs the commit through git-actor and describes the outcome for the client
+fn commit_changes(
+    request: CommitRequest,
+    author: Option<git_actor::Identity>,
+    in_memory: bool,
+    settings: &RepoSettings,
+) -> CommitResponse {
+    let changes: Vec<git_actor::FileChange> = request.changes
+        .into_iter()
+        .map(|change| match change {
+            protocol::FileChange::Write { path, content } => git_actor::FileChange::Write { path, content },
+            protocol::FileChange::Delete { path } => git_actor::FileChange::Delete { path },
+            protocol::FileChange::Rename { from, to } => git_actor::FileChange::Rename { from, to },
+        })
+        .collect();
+    let branch = request.branch.or_else(|| settings.default_branch.clone());
+    let options = git_actor::CommitOptions {
+        branch: branch.clone(),
+        create_from: request.create_branch_from,
+        expected_parent: request.expected_parent,
+        in_memory: in_memory || settings.in_memory,
+        author,
+        signing: settings.signing_key(),
+    };
+    let result = settings.path_str().and_then(|repo_path| {
+        git_actor::perform_changeset(repo_path, &changes, &request.commit_message, &options)
+    });
+    match result {
         Ok(oid) => {
             println!("Successfully created commit: {}", oid);
+            let push_result = request.push.then(|| push_commit(settings, branch.as_deref()));
             CommitResponse {
                 success: true,
                 commit_hash: Some(oid.to_string()),
                 error_message: None,
+                error_kind: None,
+                push_result,
             }
         }
         Err(e) => {
             eprintln!("Failed to perform commit: {:?}", e);
+            let error_kind = match e.downcast_ref::<git_actor::CommitError>() {
+                Some(git_actor::CommitError::Conflict { .. }) => ErrorKind::Conflict,
+                Some(git_actor::CommitError::InvalidPath { .. }) => ErrorKind::InvalidPath,
+                None => ErrorKind::Failed,
+            };
             CommitResponse {
                 success: false,
                 commit_hash: None,
                 error_message: Some(e.to_string()),
+                error_kind: Some(error_kind),
+                push_result: None,
+            }
+        }
+    }
+}
+
+// Pushes a freshly created commit to the repository's configured remote
+fn push_commit(settings: &RepoSettings, branch: Option<&str>) -> PushResult {
+    let push_settings = match &settings.push {
+        Some(push_settings) => push_settings,
+        None => {
+            return PushResult {
+                success: false,
+                error_message: Some("Pushing is not configured for this repository".to_string()),
             }
         }
     };
-    let response_message = NetworkMessage::Response(response);
-    if let Ok(json) = serde_json::to_string(&response_message) {
-        if let Err(e) = gossipsub.publish(topic, json.as_bytes()) {
-            eprintln!("Failed to publish response: {:?}", e);
-        } else {
-            println!("Published commit response.");
+    let result = settings.path_str().and_then(|repo_path| {
+        git_actor::push_branch(repo_path, &push_settings.remote, branch, &push_settings.credentials())
+    });
+    match result {
+        Ok(()) => PushResult { success: true, error_message: None },
+        Err(e) => {
+            eprintln!("Failed to push commit: {:?}", e);
+            PushResult { success: false, error_message: Some(format!("{:#}", e)) }
         }
     }
-}
\ No newline at end of file
+}
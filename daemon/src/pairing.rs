@@ -0,0 +1,219 @@
+// One-time pairing codes. `daemon pair-code` issues them into pairing_codes.json in the
+// data directory, and the running daemon redeems them when a client pairs, so nobody
+// has to sit at the daemon's terminal to approve a new client.
+use anyhow::{Context, Result};
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::files;
+use crate::peers::AuthorIdentity;
+use crate::policy::PeerPolicy;
+
+const CODES_FILE: &str = "pairing_codes.json";
+// Written by the running daemon, so `daemon pair-code` can put its addresses in the pairing URI
+const ADDRESSES_FILE: &str = "listen_addresses";
+// No 0/O or 1/I, so codes survive being read out loud
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PairingCode {
+    pub code: String,
+    // Unix time after which the code is no longer accepted
+    pub expires_at: u64,
+    // Label for the paired peer, see `TrustedPeer`
+    #[serde(default)]
+    pub label: Option<String>,
+    // How many seconds the paired peer stays trusted. `None` is forever.
+    #[serde(default)]
+    pub trusted_for: Option<u64>,
+    // Stamped as the author of the paired peer's commits. `None` uses the repo's committer.
+    pub author: Option<AuthorIdentity>,
+    // What the paired peer may do
+    #[serde(default)]
+    pub policy: PeerPolicy,
+}
+
+impl PairingCode {
+    // The code split in two halves, the way it is shown to people
+    pub fn display(&self) -> String {
+        let (first, second) = self.code.split_at(CODE_LENGTH / 2);
+        format!("{}-{}", first, second)
+    }
+}
+
+pub struct PairingCodes {
+    path: PathBuf,
+}
+
+impl PairingCodes {
+    pub fn new(data_dir: &Path) -> Self {
+        Self { path: data_dir.join(CODES_FILE) }
+    }
+
+    // Creates a code that pairs one client within `valid_for`
+    pub fn issue(
+        &self,
+        valid_for: Duration,
+        label: Option<String>,
+        trusted_for: Option<u64>,
+        author: Option<AuthorIdentity>,
+        policy: PeerPolicy,
+    ) -> Result<PairingCode> {
+        let mut rng = rand::thread_rng();
+        let code = (0..CODE_LENGTH)
+            .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+            .collect();
+        let expires_at = unix_now() + valid_for.as_secs();
+        let issued = PairingCode { code, expires_at, label, trusted_for, author, policy };
+        // The running daemon may be redeeming a code at the same time
+        let _lock = files::lock(&self.path)?;
+        let mut codes = self.load_unexpired()?;
+        codes.push(issued.clone());
+        self.save(&codes)?;
+        Ok(issued)
+    }
+
+    // Uses up `code`. `None` if it was never issued, has expired or was already used.
+    pub fn redeem(&self, code: &str) -> Result<Option<PairingCode>> {
+        let code = normalize(code);
+        let _lock = files::lock(&self.path)?;
+        let mut codes = self.load_unexpired()?;
+        let redeemed = match codes.iter().position(|issued| issued.code == code) {
+            Some(index) => codes.remove(index),
+            // Anyone can send a pairing request, so a wrong guess doesn't touch the file
+            None => return Ok(None),
+        };
+        self.save(&codes)?;
+        Ok(Some(redeemed))
+    }
+
+    fn load_unexpired(&self) -> Result<Vec<PairingCode>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file_content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {:?}", self.path))?;
+        let codes: Vec<PairingCode> = serde_json::from_str(&file_content)
+            .with_context(|| format!("Failed to parse {:?}", self.path))?;
+        let now = unix_now();
+        Ok(codes.into_iter().filter(|issued| issued.expires_at > now).collect())
+    }
+
+    fn save(&self, codes: &[PairingCode]) -> Result<()> {
+        let json = serde_json::to_string_pretty(codes)?;
+        files::write_private(&self.path, json.as_bytes())
+    }
+}
+
+// Records where the running daemon listens, one multiaddr per line
+pub fn save_listen_addresses(data_dir: &Path, addresses: &[Multiaddr]) -> Result<()> {
+    let contents: String = addresses.iter().map(|address| format!("{}\n", address)).collect();
+    files::write_private(&data_dir.join(ADDRESSES_FILE), contents.as_bytes())
+}
+
+// Forgets the addresses when the daemon stops, so `daemon pair-code` doesn't hand out dead ports
+pub fn remove_listen_addresses(data_dir: &Path) -> Result<()> {
+    let path = data_dir.join(ADDRESSES_FILE);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e).with_context(|| format!("Failed to remove {:?}", path)),
+        _ => Ok(()),
+    }
+}
+
+// The addresses a client can reach the daemon on, best first. Loopback addresses only
+// help clients on the same machine, so they are left out unless there is nothing else.
+pub fn load_listen_addresses(data_dir: &Path) -> Result<Vec<Multiaddr>> {
+    let path = data_dir.join(ADDRESSES_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let addresses: Vec<Multiaddr> = contents.lines().filter_map(|line| line.parse().ok()).collect();
+    let is_loopback = |address: &Multiaddr| match address.iter().next() {
+        Some(Protocol::Ip4(ip)) => ip.is_loopback(),
+        Some(Protocol::Ip6(ip)) => ip.is_loopback(),
+        _ => false,
+    };
+    if addresses.iter().all(is_loopback) {
+        return Ok(addresses);
+    }
+    Ok(addresses.into_iter().filter(|address| !is_loopback(address)).collect())
+}
+
+// People type codes in any case, with or without the dash
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(codes: &PairingCodes) -> PairingCode {
+        codes.issue(Duration::from_secs(600), None, None, None, PeerPolicy::default()).unwrap()
+    }
+
+    #[test]
+    fn codes_pair_only_once() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let codes = PairingCodes::new(data_dir.path());
+        let first = issue(&codes);
+        let second = issue(&codes);
+
+        // Typed the way people read it out, in lower case and with the dash
+        let redeemed = codes.redeem(&first.display().to_lowercase()).unwrap().unwrap();
+        assert_eq!(redeemed.code, first.code);
+        assert!(codes.redeem(&first.code).unwrap().is_none());
+        assert!(codes.redeem(&second.code).unwrap().is_some());
+    }
+
+    #[test]
+    fn wrong_code_leaves_the_file_alone() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let codes = PairingCodes::new(data_dir.path());
+        issue(&codes);
+        let path = data_dir.path().join(CODES_FILE);
+        // Far enough in the past that any write would show
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+
+        assert!(codes.redeem("AAAA-AAAA").unwrap().is_none());
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn codes_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let data_dir = tempfile::tempdir().unwrap();
+        let codes = PairingCodes::new(data_dir.path());
+        issue(&codes);
+        let mode = fs::metadata(data_dir.path().join(CODES_FILE)).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn listen_addresses_are_forgotten_on_shutdown() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let addresses: Vec<Multiaddr> = vec!["/ip4/127.0.0.1/tcp/4001".parse().unwrap(), "/ip4/192.0.2.1/tcp/4001".parse().unwrap()];
+        save_listen_addresses(data_dir.path(), &addresses).unwrap();
+        // Loopback is left out when there is something better
+        assert_eq!(load_listen_addresses(data_dir.path()).unwrap(), addresses[1..]);
+
+        remove_listen_addresses(data_dir.path()).unwrap();
+        assert!(load_listen_addresses(data_dir.path()).unwrap().is_empty());
+        remove_listen_addresses(data_dir.path()).unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+// The peers the daemon trusts, kept in trusted_peers.json unless the config file names
+// another file. `daemon peers revoke` edits the same file while the daemon runs, so the
+// daemon reloads it whenever it changes on disk, and every change reloads it under a lock.
+//
+// When peers were last seen changes on every request, so it lives in a file of its own
+// next to the trust file (trusted_peers.last_seen.json), written at most once a minute per peer.
+use anyhow::{Context, Result};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::files;
+use crate::pairing::unix_now;
+use crate::policy::PeerPolicy;
+
+// Who commits made on behalf of a trusted peer are attributed to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthorIdentity {
+    pub name: String,
+    pub email: String,
+}
+
+// What we know about a trusted peer. Times are Unix seconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TrustedPeer {
+    // Lets the operator tell peers apart, e.g. "Jane's phone"
+    pub label: Option<String>,
+    // 0 for peers paired before this was recorded
+    #[serde(default)]
+    pub paired_at: u64,
+    // The peer is no longer trusted after this. `None` never expires.
+    pub expires_at: Option<u64>,
+    // Stamped as the author of this peer's commits. `None` uses the repo's committer.
+    pub author: Option<AuthorIdentity>,
+    // What the peer may do
+    #[serde(default)]
+    pub policy: PeerPolicy,
+}
+
+impl TrustedPeer {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+// trusted_peers.json used to be a plain list of PeerIds, so accept both shapes
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredPeers {
+    Records(HashMap<PeerId, TrustedPeer>),
+    Legacy(HashSet<PeerId>),
+}
+
+// How stale a peer's last seen time may get before it is written out again
+const LAST_SEEN_RESOLUTION: u64 = 60;
+
+pub struct PeerManager {
+    trusted_peers_path: PathBuf,
+    trusted_peers: HashMap<PeerId, TrustedPeer>,
+    // Modification time of the file as of the last load or save
+    loaded_version: Option<SystemTime>,
+    last_seen_path: PathBuf,
+    // When each peer last made a request the daemon accepted, in Unix seconds
+    last_seen: HashMap<PeerId, u64>,
+}
+
+impl PeerManager {
+    pub fn new(trusted_peers_path: &Path) -> Result<Self> {
+        let mut manager = Self {
+            trusted_peers_path: trusted_peers_path.to_path_buf(),
+            trusted_peers: HashMap::new(),
+            loaded_version: None,
+            last_seen_path: trusted_peers_path.with_extension("last_seen.json"),
+            last_seen: HashMap::new(),
+        };
+        manager.load()?;
+        manager.load_last_seen();
+        println!("Loaded {} trusted peers.", manager.trusted_peers.len());
+        Ok(manager)
+    }
+
+    fn load(&mut self) -> Result<()> {
+        let path = &self.trusted_peers_path;
+        if !path.exists() {
+            self.trusted_peers.clear();
+            self.loaded_version = None;
+            return Ok(());
+        }
+        let file_content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        self.trusted_peers = match serde_json::from_str(&file_content)
+            .with_context(|| format!("Failed to parse {:?}", path))?
+        {
+            StoredPeers::Records(peers) => peers,
+            StoredPeers::Legacy(peers) => peers
+                .into_iter()
+                .map(|peer_id| (peer_id, TrustedPeer::default()))
+                .collect(),
+        };
+        self.loaded_version = self.file_version();
+        Ok(())
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.trusted_peers)?;
+        files::write_private(&self.trusted_peers_path, json.as_bytes())?;
+        self.loaded_version = self.file_version();
+        Ok(())
+    }
+
+    // Only informational, so a missing or broken file starts over
+    fn load_last_seen(&mut self) {
+        self.last_seen = fs::read_to_string(&self.last_seen_path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+    }
+
+    fn file_version(&self) -> Option<SystemTime> {
+        fs::metadata(&self.trusted_peers_path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    // Picks up changes `daemon peers` made to the file since we last read it
+    fn reload_if_changed(&mut self) {
+        if self.file_version() != self.loaded_version {
+            if let Err(e) = self.load() {
+                eprintln!("[WARN] Keeping the previous trusted peers: {:#}", e);
+            }
+        }
+    }
+
+    // Whether `peer_id` may make requests right now, and if not, why, to tell the client.
+    // Also records it as last seen now.
+    pub fn admit(&mut self, peer_id: &PeerId) -> Result<(), String> {
+        self.reload_if_changed();
+        let now = unix_now();
+        let peer = match self.trusted_peers.get(peer_id) {
+            Some(peer) => peer,
+            None => return Err("This client is not paired with the daemon".to_string()),
+        };
+        if peer.is_expired(now) {
+            println!("[INFO] Trust for {} has expired.", peer_id);
+            return Err("Trust for this client has expired, pair it again".to_string());
+        }
+        let previous = self.last_seen.insert(*peer_id, now);
+        if previous.is_none_or(|previous| previous + LAST_SEEN_RESOLUTION <= now) {
+            let result = serde_json::to_string_pretty(&self.last_seen)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| files::write_private(&self.last_seen_path, json.as_bytes()));
+            if let Err(e) = result {
+                eprintln!("[WARN] Failed to record when {} was last seen: {:#}", peer_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    // When `peer_id` last made a request the daemon accepted
+    pub fn last_seen(&self, peer_id: &PeerId) -> Option<u64> {
+        self.last_seen.get(peer_id).copied()
+    }
+
+    pub fn author_of(&self, peer_id: &PeerId) -> Option<git_actor::Identity> {
+        let author = self.trusted_peers.get(peer_id)?.author.as_ref()?;
+        Some(git_actor::Identity { name: author.name.clone(), email: author.email.clone() })
+    }
+
+    // The policy of a trusted peer. Unknown peers get the default, but never get this far.
+    pub fn policy_of(&self, peer_id: &PeerId) -> PeerPolicy {
+        self.trusted_peers.get(peer_id).map(|peer| peer.policy.clone()).unwrap_or_default()
+    }
+
+    pub fn add_trusted_peer(&mut self, peer_id: PeerId, peer: TrustedPeer) -> Result<()> {
+        // Reload under the lock, so a revoke made since the last load isn't undone
+        let _lock = files::lock(&self.trusted_peers_path)?;
+        self.load()?;
+        self.trusted_peers.insert(peer_id, peer);
+        self.save()?;
+        println!("Added new trusted peer: {}. Total: {}", peer_id, self.trusted_peers.len());
+        Ok(())
+    }
+
+    // Stops trusting `peer_id`. `false` if it wasn't trusted to begin with.
+    pub fn revoke(&mut self, peer_id: &PeerId) -> Result<bool> {
+        let _lock = files::lock(&self.trusted_peers_path)?;
+        self.load()?;
+        if self.trusted_peers.remove(peer_id).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    // Every trusted peer, oldest pairing first
+    pub fn list(&self) -> Vec<(&PeerId, &TrustedPeer)> {
+        let mut peers: Vec<_> = self.trusted_peers.iter().collect();
+        peers.sort_by_key(|(peer_id, peer)| (peer.paired_at, peer_id.to_string()));
+        peers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity;
+    use std::time::Duration;
+
+    fn random_peer() -> PeerId {
+        PeerId::from(identity::Keypair::generate_ed25519().public())
+    }
+
+    #[test]
+    fn pairing_keeps_a_concurrent_revoke() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let path = data_dir.path().join("trusted_peers.json");
+        let revoked = random_peer();
+        let mut daemon = PeerManager::new(&path).unwrap();
+        daemon.add_trusted_peer(revoked, TrustedPeer::default()).unwrap();
+
+        PeerManager::new(&path).unwrap().revoke(&revoked).unwrap();
+        // As if the revoke landed after the daemon last checked the file
+        daemon.loaded_version = daemon.file_version();
+        daemon.add_trusted_peer(random_peer(), TrustedPeer::default()).unwrap();
+
+        assert!(daemon.admit(&revoked).is_err());
+        let peers = PeerManager::new(&path).unwrap();
+        assert_eq!(peers.list().len(), 1);
+        assert!(peers.list().iter().all(|(peer_id, _)| **peer_id != revoked));
+    }
+
+    #[test]
+    fn requests_leave_the_trust_file_alone() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let path = data_dir.path().join("trusted_peers.json");
+        let peer = random_peer();
+        let mut daemon = PeerManager::new(&path).unwrap();
+        daemon.add_trusted_peer(peer, TrustedPeer::default()).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        daemon.loaded_version = Some(modified);
+
+        assert!(daemon.admit(&peer).is_ok());
+        assert!(daemon.admit(&peer).is_ok());
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+        // Still shown by `daemon peers list`
+        assert!(PeerManager::new(&path).unwrap().last_seen(&peer).is_some());
+    }
+
+    #[test]
+    fn expired_peers_are_refused() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut peers = PeerManager::new(&data_dir.path().join("trusted_peers.json")).unwrap();
+        let peer = random_peer();
+        peers.add_trusted_peer(peer, TrustedPeer { expires_at: Some(unix_now() - 1), ..Default::default() }).unwrap();
+        assert!(peers.admit(&peer).unwrap_err().contains("expired"));
+        assert!(peers.admit(&random_peer()).unwrap_err().contains("not paired"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn trust_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let data_dir = tempfile::tempdir().unwrap();
+        let path = data_dir.path().join("trusted_peers.json");
+        PeerManager::new(&path).unwrap().add_trusted_peer(random_peer(), TrustedPeer::default()).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,172 @@
+// What a trusted peer may do. Pairing only establishes who a peer is; the policy
+// attached to it decides which repositories, branches and files it may touch.
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use protocol::{CommitRequest, FileChange};
+use serde::{Deserialize, Serialize};
+
+// Every limit that is `None` allows everything, which is also what peers paired
+// before policies existed get.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PeerPolicy {
+    // Repository aliases the peer may use
+    #[serde(default)]
+    pub repos: Option<Vec<String>>,
+    // Globs of branch names the peer may commit to, e.g. "mobile/*"
+    #[serde(default)]
+    pub branches: Option<Vec<String>>,
+    // Globs of file paths the peer may change, relative to the repository root, e.g. "docs/**"
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+    // Whether the peer may ask the daemon to push after committing
+    #[serde(default = "allow")]
+    pub push: bool,
+}
+
+fn allow() -> bool {
+    true
+}
+
+impl Default for PeerPolicy {
+    fn default() -> Self {
+        Self { repos: None, branches: None, paths: None, push: true }
+    }
+}
+
+impl PeerPolicy {
+    pub fn allows_repo(&self, alias: &str) -> bool {
+        self.repos.as_ref().is_none_or(|repos| repos.iter().any(|repo| repo == alias))
+    }
+
+    // Checks a commit request against the policy. `branch` is the branch the commit will
+    // actually land on, `None` if that is a detached HEAD. The error says what is not allowed.
+    pub fn check_commit(&self, request: &CommitRequest, branch: Option<&str>) -> Result<(), String> {
+        if !self.allows_repo(&request.repo) {
+            return Err(format!("This peer may not use repository {}", request.repo));
+        }
+        if let Some(patterns) = &self.branches {
+            let branch = branch.ok_or("This peer may only commit to a branch, and HEAD is detached")?;
+            if !compile(patterns)?.is_match(branch) {
+                return Err(format!("This peer may not commit to branch {}", branch));
+            }
+        }
+        if let Some(patterns) = &self.paths {
+            let allowed = compile(patterns)?;
+            for change in &request.changes {
+                let paths = match change {
+                    FileChange::Write { path, .. } | FileChange::Delete { path } => vec![path],
+                    FileChange::Rename { from, to } => vec![from, to],
+                };
+                if let Some(path) = paths.into_iter().find(|path| !allowed.is_match(path.as_str())) {
+                    return Err(format!("This peer may not change {}", path));
+                }
+            }
+        }
+        if request.push && !self.push {
+            return Err("This peer may not push".to_string());
+        }
+        Ok(())
+    }
+
+    // Fails if any glob can't be parsed, so mistakes show up when the policy is set
+    pub fn validate(&self) -> Result<(), String> {
+        for patterns in [&self.branches, &self.paths].into_iter().flatten() {
+            compile(patterns)?;
+        }
+        Ok(())
+    }
+}
+
+// `*` stays within one path segment, `**` crosses them, like in .gitignore
+fn compile(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("Invalid pattern {} in peer policy: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| format!("Invalid peer policy: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(changes: Vec<FileChange>) -> CommitRequest {
+        CommitRequest {
+            repo: "app".to_string(),
+            changes,
+            commit_message: "test".to_string(),
+            branch: None,
+            create_branch_from: None,
+            expected_parent: None,
+            push: false,
+        }
+    }
+
+    fn write(path: &str) -> FileChange {
+        FileChange::Write { path: path.to_string(), content: String::new() }
+    }
+
+    fn globs(patterns: &[&str]) -> Option<Vec<String>> {
+        Some(patterns.iter().map(|pattern| pattern.to_string()).collect())
+    }
+
+    #[test]
+    fn default_policy_allows_everything() {
+        let mut request = request(vec![write(".github/workflows/ci.yml")]);
+        request.push = true;
+        assert_eq!(PeerPolicy::default().check_commit(&request, None), Ok(()));
+    }
+
+    #[test]
+    fn single_star_stays_within_a_branch_segment() {
+        let policy = PeerPolicy { branches: globs(&["mobile/*"]), ..Default::default() };
+        let request = request(vec![write("README.md")]);
+        assert_eq!(policy.check_commit(&request, Some("mobile/notes")), Ok(()));
+        assert!(policy.check_commit(&request, Some("mobile/notes/draft")).is_err());
+        assert!(policy.check_commit(&request, Some("main")).is_err());
+        // No branch to match against
+        assert!(policy.check_commit(&request, None).is_err());
+    }
+
+    #[test]
+    fn double_star_crosses_path_segments() {
+        let policy = PeerPolicy { paths: globs(&["docs/**", "*.md"]), ..Default::default() };
+        assert_eq!(policy.check_commit(&request(vec![write("docs/guide/setup.md")]), None), Ok(()));
+        assert_eq!(policy.check_commit(&request(vec![write("README.md")]), None), Ok(()));
+        assert!(policy.check_commit(&request(vec![write("src/notes.md")]), None).is_err());
+        assert!(policy.check_commit(&request(vec![write("src/main.rs")]), None).is_err());
+    }
+
+    #[test]
+    fn every_path_of_every_change_is_checked() {
+        let policy = PeerPolicy { paths: globs(&["docs/**"]), ..Default::default() };
+        let delete = FileChange::Delete { path: "src/main.rs".to_string() };
+        assert!(policy.check_commit(&request(vec![write("docs/a.md"), delete]), None).is_err());
+        for (from, to) in [("docs/a.md", "src/a.md"), ("src/a.md", "docs/a.md")] {
+            let rename = FileChange::Rename { from: from.to_string(), to: to.to_string() };
+            let error = policy.check_commit(&request(vec![rename]), None).unwrap_err();
+            assert_eq!(error, "This peer may not change src/a.md");
+        }
+    }
+
+    #[test]
+    fn repos_and_push_are_limited() {
+        let policy = PeerPolicy { repos: globs(&["lib"]), push: false, ..Default::default() };
+        assert!(policy.check_commit(&request(vec![]), None).is_err());
+        let mut request = request(vec![]);
+        request.repo = "lib".to_string();
+        assert_eq!(policy.check_commit(&request, None), Ok(()));
+        request.push = true;
+        assert_eq!(policy.check_commit(&request, None), Err("This peer may not push".to_string()));
+    }
+
+    #[test]
+    fn invalid_globs_are_reported() {
+        let policy = PeerPolicy { branches: globs(&["feature/[unclosed"]), ..Default::default() };
+        assert!(policy.validate().is_err());
+        assert!(PeerPolicy::default().validate().is_ok());
+    }
+}
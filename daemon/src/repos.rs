@@ -0,0 +1,127 @@
+// The registry of repositories clients may commit to, loaded from repositories.json
+// unless the config file names another file.
+// Clients refer to repositories by alias, so host paths never leave the daemon.
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use protocol::RepoInfo;
+
+use crate::policy::PeerPolicy;
+
+// How commits to a repository are signed
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum SigningSettings {
+    // { "mode": "ssh", "key_path": "/home/me/.ssh/id_ed25519" }
+    Ssh { key_path: String },
+    // { "mode": "gpg", "key_id": "ABCD1234" }
+    Gpg { key_id: String },
+}
+
+// How the daemon authenticates to an SSH remote
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum CredentialSettings {
+    // { "mode": "agent" }
+    #[default]
+    Agent,
+    // { "mode": "key", "private_key": "/home/me/.ssh/id_ed25519", "passphrase": "..." }
+    Key { private_key: String, passphrase: Option<String> },
+}
+
+// Where commits are pushed when a client asks for it
+#[derive(Deserialize, Debug, Clone)]
+pub struct PushSettings {
+    #[serde(default = "default_remote")]
+    pub remote: String,
+    #[serde(default)]
+    pub credentials: CredentialSettings,
+}
+
+fn default_remote() -> String {
+    "origin".to_string()
+}
+
+impl PushSettings {
+    pub fn credentials(&self) -> git_actor::PushCredentials {
+        match &self.credentials {
+            CredentialSettings::Agent => git_actor::PushCredentials::SshAgent,
+            CredentialSettings::Key { private_key, passphrase } => git_actor::PushCredentials::SshKey {
+                private_key: private_key.clone(),
+                passphrase: passphrase.clone(),
+            },
+        }
+    }
+}
+
+// A registered repository and the policy the daemon applies to commits in it
+#[derive(Deserialize, Debug, Clone)]
+pub struct RepoSettings {
+    pub path: PathBuf,
+    // Branch commits go to when the client doesn't name one. `None` uses HEAD.
+    pub default_branch: Option<String>,
+    // Build commits in memory instead of through the working tree
+    #[serde(default)]
+    pub in_memory: bool,
+    // `None` creates unsigned commits
+    pub signing: Option<SigningSettings>,
+    // `None` means clients can't push this repository
+    pub push: Option<PushSettings>,
+}
+
+impl RepoSettings {
+    pub fn signing_key(&self) -> Option<git_actor::SigningKey> {
+        match self.signing.as_ref()? {
+            SigningSettings::Ssh { key_path } => Some(git_actor::SigningKey::Ssh { key_path: key_path.clone() }),
+            SigningSettings::Gpg { key_id } => Some(git_actor::SigningKey::Gpg { key_id: key_id.clone() }),
+        }
+    }
+
+    pub fn path_str(&self) -> anyhow::Result<&str> {
+        self.path.to_str().ok_or_else(|| anyhow::anyhow!("Repository path {:?} is not valid UTF-8", self.path))
+    }
+}
+
+// Maps an alias to a repository. It is also the allow-list: the daemon refuses
+// to touch any repository that isn't registered.
+pub struct RepoRegistry {
+    repos: BTreeMap<String, RepoSettings>,
+}
+
+impl RepoRegistry {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let repos: BTreeMap<String, RepoSettings> = if path.exists() {
+            let file_content = fs::read_to_string(path)?;
+            serde_json::from_str(&file_content)?
+        } else {
+            BTreeMap::new()
+        };
+        for (alias, settings) in &repos {
+            if !settings.path.is_dir() {
+                eprintln!("[WARN] Repository {} points at {:?}, which is not a directory", alias, settings.path);
+            }
+        }
+        println!("Loaded {} registered repositories.", repos.len());
+        Ok(Self { repos })
+    }
+
+    // `None` if no repository is registered under `alias`
+    pub fn get(&self, alias: &str) -> Option<&RepoSettings> {
+        self.repos.get(alias)
+    }
+
+    // The repositories `policy` lets a peer use
+    pub fn list(&self, policy: &PeerPolicy) -> Vec<RepoInfo> {
+        self.repos
+            .iter()
+            .filter(|(alias, _)| policy.allows_repo(alias))
+            .map(|(alias, settings)| RepoInfo {
+                alias: alias.clone(),
+                default_branch: settings.default_branch.clone(),
+                can_push: settings.push.is_some() && policy.push,
+            })
+            .collect()
+    }
+}
@@ -1,7 +1,103 @@
-use git2::{Repository, Signature, Oid}; // Removed unused Commit import
-use std::path::Path;
+use git2::{
+    Commit, Cred, Index, IndexEntry, IndexTime, PushOptions, Reference, RemoteCallbacks, Repository,
+    Signature, Oid, Tree,
+};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use std::fs;
-use anyhow::{Result, Context};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use anyhow::{anyhow, Result, Context};
+use thiserror::Error;
+
+/// Errors that callers may want to handle differently from a generic failure.
+#[derive(Debug, Error)]
+pub enum CommitError {
+    /// The target ref no longer points at the commit the changes were based on.
+    #[error("Conflict: {ref_name} is at {actual}, but the changes were based on {expected}")]
+    Conflict {
+        ref_name: String,
+        expected: String,
+        actual: String,
+    },
+    /// A path in the changeset could leave the repository or touch git's own files.
+    #[error("Invalid path {path:?}: {reason}")]
+    InvalidPath {
+        path: String,
+        reason: &'static str,
+    },
+}
+
+/// A single operation that is part of a changeset.
+#[derive(Debug, Clone)]
+pub enum FileChange {
+    /// Overwrite (or create) the file at `path` with `content`.
+    Write { path: String, content: String },
+    /// Remove the file at `path`.
+    Delete { path: String },
+    /// Move the file at `from` to `to`, keeping its content.
+    Rename { from: String, to: String },
+}
+
+/// A name and email pair, used as the author of a commit.
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+/// A key used to sign commits, like git's `gpg.format` and `user.signingkey`.
+#[derive(Debug, Clone)]
+pub enum SigningKey {
+    /// Sign with `ssh-keygen -Y sign` using the private key file at `key_path`.
+    Ssh { key_path: String },
+    /// Sign with `gpg --detach-sign` using the OpenPGP key `key_id`.
+    Gpg { key_id: String },
+}
+
+/// How to authenticate to a remote when pushing over SSH.
+#[derive(Debug, Clone, Default)]
+pub enum PushCredentials {
+    /// Ask the running SSH agent for a key.
+    #[default]
+    SshAgent,
+    /// Use the private key file at `private_key`, unlocked with `passphrase` if set.
+    SshKey { private_key: String, passphrase: Option<String> },
+}
+
+/// Controls where a changeset is committed.
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    /// Branch to commit to. `None` commits to whatever HEAD points at.
+    pub branch: Option<String>,
+    /// Revision to create `branch` from when it doesn't exist yet.
+    /// Ignored when the branch already exists.
+    pub create_from: Option<String>,
+    /// Commit id the changes were based on. If the target ref has moved since,
+    /// the commit is rejected with [`CommitError::Conflict`].
+    pub expected_parent: Option<String>,
+    /// Build the new tree in memory even for the checked-out branch, and only
+    /// move the ref. The working directory and index are never written, so
+    /// uncommitted local edits survive (they will show the new commit as a
+    /// difference until the user updates their checkout). Bare repositories
+    /// always use this mode.
+    pub in_memory: bool,
+    /// Who made the change. `None` uses the committer, which always comes from
+    /// the repository's git config.
+    pub author: Option<Identity>,
+    /// Sign the commit with this key. `None` creates an unsigned commit.
+    pub signing: Option<SigningKey>,
+}
+
+impl FileChange {
+    /// Every repository path this change touches.
+    fn paths(&self) -> Vec<&str> {
+        match self {
+            FileChange::Write { path, .. } | FileChange::Delete { path } => vec![path],
+            FileChange::Rename { from, to } => vec![from, to],
+        }
+    }
+}
 
 /// Performs a full add-and-commit cycle for a single file.
 pub fn perform_commit(
@@ -10,52 +106,963 @@ pub fn perform_commit(
     new_content: &str,
     commit_message: &str,
 ) -> Result<Oid> { // Returns the Oid (hash) of the new commit on success
+    let change = FileChange::Write {
+        path: file_to_commit_str.to_string(),
+        content: new_content.to_string(),
+    };
+    perform_changeset(repo_path_str, &[change], commit_message, &CommitOptions::default())
+}
+
+/// Applies every change in `changes` and records all of them in a single commit.
+///
+/// When the target branch is the one checked out, the changes are written to
+/// the working directory and staged as usual. Any other branch, a bare
+/// repository, or [`CommitOptions::in_memory`] builds the new tree in memory
+/// from the parent commit, so the working tree is left untouched.
+pub fn perform_changeset(
+    repo_path_str: &str,
+    changes: &[FileChange],
+    commit_message: &str,
+    options: &CommitOptions,
+) -> Result<Oid> { // Returns the Oid (hash) of the new commit on success
+    if changes.is_empty() {
+        return Err(anyhow!("Refusing to create a commit with no file changes"));
+    }
+
+    // Reject anything that could escape the repository before touching it
+    for change in changes {
+        for path in change.paths() {
+            validate_path(path)?;
+        }
+    }
 
     // 1. Open the repository
     let repo = Repository::open(repo_path_str)
         .with_context(|| format!("Failed to open repository at {}", repo_path_str))?;
-    
-    // 2. Write the new content to the file inside the repository's working directory
-    let repo_path = Path::new(repo_path_str);
-    let file_path = repo_path.join(file_to_commit_str);
-    fs::write(&file_path, new_content)
-        .with_context(|| format!("Failed to write to file {:?}", file_path))?;
-
-    // 3. Get the repository index (the staging area)
+
+    // 2. Work out which ref we are committing to and what its parent commit is
+    let target = resolve_target(&repo, options)?;
+
+    // 3. Make sure nobody else moved the target since the client looked at it
+    if let Some(expected) = &options.expected_parent {
+        let expected_oid = Oid::from_str(expected)
+            .with_context(|| format!("Invalid expected parent commit id: {}", expected))?;
+        if expected_oid != target.parent.id() {
+            return Err(CommitError::Conflict {
+                ref_name: target.ref_name,
+                expected: expected.clone(),
+                actual: target.parent.id().to_string(),
+            }.into());
+        }
+    }
+
+    // 4. Build the new tree, either through the working directory or in memory
+    let tree_id = if target.checked_out && !options.in_memory {
+        stage_in_working_tree(&repo, changes)?
+    } else {
+        build_tree_in_memory(&repo, &target.parent, changes)?
+    };
+    let tree = repo.find_tree(tree_id)
+        .with_context(|| "Failed to find the new tree in repository")?;
+
+    // 5. Create the signatures for the commit
+    let committer = committer_signature(&repo)?;
+    let author = match &options.author {
+        Some(identity) => Signature::now(&identity.name, &identity.email)
+            .with_context(|| format!("Invalid author identity {} <{}>", identity.name, identity.email))?,
+        None => committer.clone(),
+    };
+
+    // 6. Create the commit and move the target ref to it
+    let new_commit_oid = match &options.signing {
+        None => repo.commit(
+            Some(&target.ref_name),
+            &author,
+            &committer,
+            commit_message,
+            &tree,
+            &[&target.parent], // Array of parent commits
+        )?,
+        Some(key) => commit_signed(&repo, &target, key, &author, &committer, commit_message, &tree)?,
+    };
+
+    println!(
+        "Successfully created commit: {} on {} ({} file(s))",
+        new_commit_oid, target.ref_name, changes.len()
+    );
+    Ok(new_commit_oid)
+}
+
+/// The committer is whoever the repository's git config (`user.name` and
+/// `user.email`) says, falling back to a fixed identity when none is set.
+fn committer_signature(repo: &Repository) -> Result<Signature<'static>> {
+    match repo.signature() {
+        Ok(signature) => Ok(signature),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            println!("No user.name/user.email in git config, committing as Emergency Committer");
+            Ok(Signature::now("Emergency Committer", "emergency@example.com")?)
+        }
+        Err(e) => Err(e).with_context(|| "Failed to read committer identity from git config"),
+    }
+}
+
+/// Writes a signed commit and moves the target ref to it, as `repo.commit` would.
+fn commit_signed(
+    repo: &Repository,
+    target: &CommitTarget,
+    key: &SigningKey,
+    author: &Signature,
+    committer: &Signature,
+    commit_message: &str,
+    tree: &Tree,
+) -> Result<Oid> {
+    let buffer = repo.commit_create_buffer(author, committer, commit_message, tree, &[&target.parent])
+        .with_context(|| "Failed to create commit buffer")?;
+    let content = buffer.as_str()
+        .ok_or_else(|| anyhow!("Commit buffer is not valid UTF-8"))?;
+    let signature = sign_buffer(content, key)?;
+    let commit_oid = repo.commit_signed(content, &signature, None)
+        .with_context(|| "Failed to write signed commit")?;
+
+    let log_message = format!("commit: {}", commit_message.lines().next().unwrap_or_default());
+    match repo.find_reference(&target.ref_name) {
+        // Follow HEAD to its branch, and only move it if nobody else did in the meantime
+        Ok(reference) => {
+            let resolved = reference.resolve()
+                .with_context(|| format!("Failed to resolve {}", target.ref_name))?;
+            let resolved_name = resolved.name()
+                .ok_or_else(|| anyhow!("Ref name of {} is not valid UTF-8", target.ref_name))?;
+            repo.reference_matching(resolved_name, commit_oid, true, target.parent.id(), &log_message)
+                .with_context(|| format!("Failed to update {}", resolved_name))?;
+        }
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            repo.reference(&target.ref_name, commit_oid, false, &log_message)
+                .with_context(|| format!("Failed to create {}", target.ref_name))?;
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to look up {}", target.ref_name)),
+    }
+    Ok(commit_oid)
+}
+
+/// Produces an armored detached signature of `content` with the external signing tool for `key`.
+fn sign_buffer(content: &str, key: &SigningKey) -> Result<String> {
+    let mut command = match key {
+        SigningKey::Ssh { key_path } => {
+            let mut command = Command::new("ssh-keygen");
+            command.args(["-Y", "sign", "-n", "git", "-f", key_path]);
+            command
+        }
+        SigningKey::Gpg { key_id } => {
+            let mut command = Command::new("gpg");
+            command.args(["--status-fd=2", "-bsau", key_id]);
+            command
+        }
+    };
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    // Dropping stdin after writing closes it, so the signer knows the input is complete
+    child.stdin.take()
+        .ok_or_else(|| anyhow!("Failed to open stdin of {}", program))?
+        .write_all(content.as_bytes())
+        .with_context(|| format!("Failed to pass commit to {}", program))?;
+    let output = child.wait_with_output()
+        .with_context(|| format!("Failed to wait for {}", program))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed to sign the commit: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("Signature from {} is not valid UTF-8", program))
+}
+
+/// The ref a changeset is committed to, along with its parent commit.
+struct CommitTarget<'repo> {
+    ref_name: String,
+    parent: Commit<'repo>,
+    /// Whether `ref_name` is what the working directory has checked out.
+    checked_out: bool,
+}
+
+fn resolve_target<'repo>(repo: &'repo Repository, options: &CommitOptions) -> Result<CommitTarget<'repo>> {
+    let branch = match &options.branch {
+        Some(branch) => branch,
+        None => {
+            let head = repo.head()
+                .with_context(|| "Failed to get repository HEAD")?;
+            let parent = head.peel_to_commit()
+                .with_context(|| "Failed to peel HEAD to a commit")?;
+            return Ok(CommitTarget { ref_name: "HEAD".to_string(), parent, checked_out: !repo.is_bare() });
+        }
+    };
+
+    let ref_name = format!("refs/heads/{}", branch);
+    if !Reference::is_valid_name(&ref_name) {
+        return Err(anyhow!("Invalid branch name: {}", branch));
+    }
+
+    let parent = match repo.find_reference(&ref_name) {
+        Ok(reference) => reference.peel_to_commit()
+            .with_context(|| format!("Failed to peel {} to a commit", ref_name))?,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            let base = options.create_from.as_deref()
+                .ok_or_else(|| anyhow!("Branch {} does not exist and no base to create it from was given", branch))?;
+            repo.revparse_single(base)
+                .and_then(|object| object.peel_to_commit())
+                .with_context(|| format!("Failed to resolve base revision {}", base))?
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to look up branch {}", branch)),
+    };
+
+    let checked_out = !repo.is_bare()
+        && repo.head().ok().and_then(|head| head.name().map(|name| name == ref_name)).unwrap_or(false);
+    Ok(CommitTarget { ref_name, parent, checked_out })
+}
+
+/// Writes the changes to the working directory, stages them and returns the resulting tree.
+/// Every change is checked before the first file is written, so a bad change late in the
+/// changeset can't leave the earlier ones behind in the checkout.
+fn stage_in_working_tree(repo: &Repository, changes: &[FileChange]) -> Result<Oid> {
+    // Canonical, so symlinked paths can be compared against it
+    let workdir = repo.workdir()
+        .ok_or_else(|| anyhow!("Repository has no working directory"))?;
+    let workdir = fs::canonicalize(workdir)
+        .with_context(|| format!("Failed to resolve working directory {:?}", workdir))?;
     let mut index = repo.index()
         .with_context(|| "Failed to get repository index")?;
 
-    // 4. Stage the file
-    let file_path_in_repo = Path::new(file_to_commit_str);
-    index.add_path(file_path_in_repo)
-        .with_context(|| format!("Failed to add file to index: {:?}", file_path_in_repo))?;
+    check_changes(&workdir, &index, changes)?;
+    for change in changes {
+        apply_change(&workdir, &mut index, change)?;
+    }
+
+    // Only write the index once every change was staged
     index.write()
         .with_context(|| "Failed to write index to disk")?;
-    let tree_id = index.write_tree()
-        .with_context(|| "Failed to write index as tree")?;
-    let tree = repo.find_tree(tree_id)
-        .with_context(|| "Failed to find the new tree in repository")?;
+    index.write_tree()
+        .with_context(|| "Failed to write index as tree")
+}
+
+/// Builds the tree of `parent` plus the changes in an in-memory index, without touching the disk.
+fn build_tree_in_memory(repo: &Repository, parent: &Commit, changes: &[FileChange]) -> Result<Oid> {
+    let mut index = Index::new()
+        .with_context(|| "Failed to create in-memory index")?;
+    index.read_tree(&parent.tree()?)
+        .with_context(|| "Failed to read parent tree into index")?;
+
+    for change in changes {
+        apply_change_in_memory(repo, &mut index, change)?;
+    }
 
-    // 5. Find the parent commit (the current HEAD)
+    index.write_tree_to(repo)
+        .with_context(|| "Failed to write in-memory index as tree")
+}
+
+/// The name of the branch HEAD points at, or `None` if HEAD is detached.
+pub fn head_branch(repo_path_str: &str) -> Result<Option<String>> {
+    let repo = Repository::open(repo_path_str)
+        .with_context(|| format!("Failed to open repository at {}", repo_path_str))?;
     let head = repo.head()
         .with_context(|| "Failed to get repository HEAD")?;
-    let parent_commit = head.peel_to_commit()
-        .with_context(|| "Failed to peel HEAD to a commit")?;
-
-    // 6. Create the signature for the commit
-    // In a real app, you'd get this from Git config. We'll hardcode it for now.
-    let signature = Signature::now("Emergency Committer", "emergency@example.com")?;
-
-    // 7. Create the commit
-    let new_commit_oid = repo.commit(
-        Some("HEAD"), // Update HEAD to point to this new commit
-        &signature,   // Author
-        &signature,   // Committer
-        commit_message,
-        &tree,
-        &[&parent_commit], // Array of parent commits
-    )?;
-
-    println!("Successfully created commit: {}", new_commit_oid);
-    Ok(new_commit_oid)
-}
\ No newline at end of file
+    if !head.is_branch() {
+        return Ok(None);
+    }
+    Ok(head.shorthand().map(str::to_string))
+}
+
+/// Pushes `branch` to the same branch on `remote_name`. `None` pushes the
+/// branch HEAD points at. Local remotes (e.g. a bare repository on disk) need
+/// no credentials; SSH remotes authenticate with `credentials`.
+pub fn push_branch(
+    repo_path_str: &str,
+    remote_name: &str,
+    branch: Option<&str>,
+    credentials: &PushCredentials,
+) -> Result<()> {
+    let repo = Repository::open(repo_path_str)
+        .with_context(|| format!("Failed to open repository at {}", repo_path_str))?;
+
+    let ref_name = match branch {
+        Some(branch) => format!("refs/heads/{}", branch),
+        None => {
+            let head = repo.head()
+                .with_context(|| "Failed to get repository HEAD")?;
+            if !head.is_branch() {
+                return Err(anyhow!("HEAD is detached, so there is no branch to push"));
+            }
+            head.name()
+                .ok_or_else(|| anyhow!("HEAD branch name is not valid UTF-8"))?
+                .to_string()
+        }
+    };
+    let refspec = format!("{}:{}", ref_name, ref_name);
+
+    let mut remote = repo.find_remote(remote_name)
+        .with_context(|| format!("Failed to find remote {}", remote_name))?;
+
+    // The server reports rejected refs (e.g. non-fast-forward) through a callback, not as an error
+    let mut rejection = None;
+    {
+        let mut callbacks = RemoteCallbacks::new();
+        let mut asked_for_credentials = false;
+        callbacks.credentials(|_url, username_from_url, _allowed_types| {
+            // libgit2 keeps asking as long as we return credentials, so only offer them once
+            if asked_for_credentials {
+                return Err(git2::Error::from_str("Authentication with the configured credentials failed"));
+            }
+            asked_for_credentials = true;
+            let username = username_from_url.unwrap_or("git");
+            match credentials {
+                PushCredentials::SshAgent => Cred::ssh_key_from_agent(username),
+                PushCredentials::SshKey { private_key, passphrase } => {
+                    Cred::ssh_key(username, None, Path::new(private_key), passphrase.as_deref())
+                }
+            }
+        });
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(message) = status {
+                rejection = Some(format!("{}: {}", refname, message));
+            }
+            Ok(())
+        });
+
+        let mut push_options = PushOptions::new();
+        push_options.remote_callbacks(callbacks);
+        remote.push(&[refspec.as_str()], Some(&mut push_options))
+            .with_context(|| format!("Failed to push {} to {}", ref_name, remote_name))?;
+    }
+
+    if let Some(rejection) = rejection {
+        return Err(anyhow!("Remote {} rejected the push of {}", remote_name, rejection));
+    }
+    println!("Successfully pushed {} to {}", ref_name, remote_name);
+    Ok(())
+}
+
+/// Checks, without writing anything, that the changes can be applied to the working
+/// directory in order: every path stays inside it, nothing is written through a symlink
+/// or over a directory, and deleted or renamed files are tracked and on disk.
+fn check_changes(workdir: &Path, index: &Index, changes: &[FileChange]) -> Result<()> {
+    // Whether a path exists once the changes checked so far are applied
+    let mut exists: HashMap<&str, bool> = HashMap::new();
+    let check_tracked = |path: &str, exists: &HashMap<&str, bool>, action: &str| -> Result<()> {
+        let tracked = exists.get(path).copied().unwrap_or_else(|| {
+            index.get_path(Path::new(path), 0).is_some() && fs::symlink_metadata(workdir.join(path)).is_ok()
+        });
+        if !tracked {
+            return Err(anyhow!("Cannot {} {:?}: file is not tracked", action, path));
+        }
+        Ok(())
+    };
+    // A file can't replace a directory or go below another file
+    let check_no_clash = |path: &str, exists: &HashMap<&str, bool>| -> Result<()> {
+        if fs::symlink_metadata(workdir.join(path)).is_ok_and(|metadata| metadata.is_dir()) {
+            return Err(anyhow!("Cannot write {:?}: it is a directory", path));
+        }
+        for ancestor in Path::new(path).ancestors().skip(1).filter(|ancestor| !ancestor.as_os_str().is_empty()) {
+            let ancestor = ancestor.to_str().unwrap_or_default();
+            let is_file = exists.get(ancestor).copied().unwrap_or_else(|| {
+                fs::symlink_metadata(workdir.join(ancestor)).is_ok_and(|metadata| !metadata.is_dir())
+            });
+            if is_file {
+                return Err(anyhow!("Cannot write {:?}: {:?} is a file", path, ancestor));
+            }
+        }
+        Ok(())
+    };
+    for change in changes {
+        for path in change.paths() {
+            path_in_workdir(workdir, path)?;
+        }
+        match change {
+            FileChange::Write { path, .. } => {
+                // fs::write follows symlinks, which could point anywhere on the host
+                if fs::symlink_metadata(workdir.join(path)).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+                    return Err(CommitError::InvalidPath { path: path.clone(), reason: "refusing to write through a symlink" }.into());
+                }
+                check_no_clash(path, &exists)?;
+                exists.insert(path, true);
+            }
+            FileChange::Delete { path } => {
+                check_tracked(path, &exists, "delete")?;
+                exists.insert(path, false);
+            }
+            FileChange::Rename { from, to } => {
+                check_tracked(from, &exists, "rename")?;
+                check_no_clash(to, &exists)?;
+                exists.insert(from, false);
+                exists.insert(to, true);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies a single change to the working directory and stages it in `index`.
+/// The change must have passed [`check_changes`].
+fn apply_change(workdir: &Path, index: &mut Index, change: &FileChange) -> Result<()> {
+    match change {
+        FileChange::Write { path, content } => {
+            let file_path = path_in_workdir(workdir, path)?;
+            create_parent_dirs(&file_path)?;
+            fs::write(&file_path, content)
+                .with_context(|| format!("Failed to write to file {:?}", file_path))?;
+            index.add_path(Path::new(path))
+                .with_context(|| format!("Failed to add file to index: {:?}", path))?;
+        }
+        FileChange::Delete { path } => {
+            let file_path = path_in_workdir(workdir, path)?;
+            fs::remove_file(&file_path)
+                .with_context(|| format!("Failed to delete file {:?}", file_path))?;
+            index.remove_path(Path::new(path))
+                .with_context(|| format!("Failed to remove file from index: {:?}", path))?;
+        }
+        FileChange::Rename { from, to } => {
+            let from_path = path_in_workdir(workdir, from)?;
+            let to_path = path_in_workdir(workdir, to)?;
+            create_parent_dirs(&to_path)?;
+            fs::rename(&from_path, &to_path)
+                .with_context(|| format!("Failed to rename {:?} to {:?}", from_path, to_path))?;
+            index.remove_path(Path::new(from))
+                .with_context(|| format!("Failed to remove file from index: {:?}", from))?;
+            index.add_path(Path::new(to))
+                .with_context(|| format!("Failed to add file to index: {:?}", to))?;
+        }
+    }
+    Ok(())
+}
+
+/// Rejects paths that are empty, absolute, contain `..` or `.`, or enter a `.git` directory.
+fn validate_path(path: &str) -> Result<(), CommitError> {
+    let invalid = |reason| CommitError::InvalidPath { path: path.to_string(), reason };
+    if path.is_empty() {
+        return Err(invalid("path is empty"));
+    }
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(name) if name.eq_ignore_ascii_case(".git") => {
+                return Err(invalid("path enters the .git directory"));
+            }
+            Component::Normal(_) => {}
+            Component::ParentDir => return Err(invalid("path leaves the repository")),
+            Component::CurDir => return Err(invalid("path contains '.'")),
+            Component::RootDir | Component::Prefix(_) => return Err(invalid("path is absolute")),
+        }
+    }
+    Ok(())
+}
+
+/// Joins a validated `path` onto the canonical `workdir`, making sure no
+/// symlinked directory along the way leads outside of it.
+fn path_in_workdir(workdir: &Path, path: &str) -> Result<PathBuf> {
+    let full_path = workdir.join(path);
+    // Directories that don't exist yet will be created below the deepest one that does
+    let mut existing = full_path.parent();
+    while let Some(dir) = existing {
+        if dir.exists() {
+            break;
+        }
+        existing = dir.parent();
+    }
+    let existing = existing.unwrap_or(workdir);
+    let resolved = fs::canonicalize(existing)
+        .with_context(|| format!("Failed to resolve {:?}", existing))?;
+    if !resolved.starts_with(workdir) {
+        return Err(CommitError::InvalidPath {
+            path: path.to_string(),
+            reason: "path leaves the repository through a symlink",
+        }.into());
+    }
+    Ok(full_path)
+}
+
+/// Makes sure the directory that will contain `file_path` exists.
+fn create_parent_dirs(file_path: &Path) -> Result<()> {
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    Ok(())
+}
+
+/// Applies a single change to an in-memory index, storing new content as blobs.
+fn apply_change_in_memory(repo: &Repository, index: &mut Index, change: &FileChange) -> Result<()> {
+    match change {
+        FileChange::Write { path, content } => {
+            let blob_id = repo.blob(content.as_bytes())
+                .with_context(|| format!("Failed to store content of {:?}", path))?;
+            // Keep the mode of an existing file (e.g. executable), default to a regular file
+            let mode = index.get_path(Path::new(path), 0).map(|entry| entry.mode).unwrap_or(0o100644);
+            check_no_clash_in_index(index, path)?;
+            index.add(&index_entry(path, blob_id, mode))
+                .with_context(|| format!("Failed to add file to index: {:?}", path))?;
+        }
+        FileChange::Delete { path } => {
+            if index.get_path(Path::new(path), 0).is_none() {
+                return Err(anyhow!("Cannot delete {:?}: file is not tracked", path));
+            }
+            index.remove_path(Path::new(path))
+                .with_context(|| format!("Failed to remove file from index: {:?}", path))?;
+        }
+        FileChange::Rename { from, to } => {
+            let entry = index.get_path(Path::new(from), 0)
+                .ok_or_else(|| anyhow!("Cannot rename {:?}: file is not tracked", from))?;
+            index.remove_path(Path::new(from))
+                .with_context(|| format!("Failed to remove file from index: {:?}", from))?;
+            check_no_clash_in_index(index, to)?;
+            index.add(&index_entry(to, entry.id, entry.mode))
+                .with_context(|| format!("Failed to add file to index: {:?}", to))?;
+        }
+    }
+    Ok(())
+}
+
+/// Rejects adding a file at `path` where the index has a directory, or below a file.
+/// `Index::add` would otherwise drop the clashing entries without a word.
+fn check_no_clash_in_index(index: &Index, path: &str) -> Result<()> {
+    let dir_prefix = format!("{}/", path);
+    if index.iter().any(|entry| entry.path.starts_with(dir_prefix.as_bytes())) {
+        return Err(anyhow!("Cannot write {:?}: it is a directory", path));
+    }
+    for ancestor in Path::new(path).ancestors().skip(1).filter(|ancestor| !ancestor.as_os_str().is_empty()) {
+        if index.get_path(ancestor, 0).is_some() {
+            return Err(anyhow!("Cannot write {:?}: {:?} is a file", path, ancestor));
+        }
+    }
+    Ok(())
+}
+
+/// An index entry for a blob that has no counterpart in the working directory.
+fn index_entry(path: &str, id: Oid, mode: u32) -> IndexEntry {
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id,
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A repository with a committer in its config and one commit holding `README.md`.
+    fn init_repo() -> (TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        {
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "Test").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+        }
+        fs::write(dir.path().join("README.md"), "hello\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("README.md")).unwrap();
+        index.write().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        {
+            let tree = repo.find_tree(tree_id).unwrap();
+            let signature = repo.signature().unwrap();
+            repo.commit(Some("HEAD"), &signature, &signature, "Initial commit", &tree, &[]).unwrap();
+        }
+        (dir, repo)
+    }
+
+    fn repo_path(dir: &TempDir) -> &str {
+        dir.path().to_str().unwrap()
+    }
+
+    fn write(path: &str, content: &str) -> FileChange {
+        FileChange::Write { path: path.to_string(), content: content.to_string() }
+    }
+
+    fn head_id(repo: &Repository) -> Oid {
+        repo.head().unwrap().target().unwrap()
+    }
+
+    #[test]
+    fn failed_changeset_leaves_working_tree_untouched() {
+        let (dir, repo) = init_repo();
+        let head = head_id(&repo);
+        let changes = [write("notes.txt", "new\n"), FileChange::Delete { path: "missing.txt".to_string() }];
+        assert!(perform_changeset(repo_path(&dir), &changes, "Partial", &CommitOptions::default()).is_err());
+        assert!(!dir.path().join("notes.txt").exists());
+        assert_eq!(head_id(&repo), head);
+    }
+
+    #[test]
+    fn untracked_rename_source_is_rejected_before_writing() {
+        let (dir, _repo) = init_repo();
+        fs::write(dir.path().join("local.txt"), "not committed\n").unwrap();
+        let changes = [
+            write("README.md", "changed\n"),
+            FileChange::Rename { from: "local.txt".to_string(), to: "moved.txt".to_string() },
+        ];
+        assert!(perform_changeset(repo_path(&dir), &changes, "Rename", &CommitOptions::default()).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "hello\n");
+        assert!(dir.path().join("local.txt").exists());
+        assert!(!dir.path().join("moved.txt").exists());
+    }
+
+    #[test]
+    fn changes_see_earlier_changes_in_the_changeset() {
+        let (dir, repo) = init_repo();
+        let changes = [
+            write("draft.txt", "text\n"),
+            FileChange::Rename { from: "draft.txt".to_string(), to: "final.txt".to_string() },
+            FileChange::Delete { path: "README.md".to_string() },
+        ];
+        let commit_id = perform_changeset(repo_path(&dir), &changes, "Rework", &CommitOptions::default()).unwrap();
+        assert_eq!(head_id(&repo), commit_id);
+        let tree = repo.find_commit(commit_id).unwrap().tree().unwrap();
+        assert!(tree.get_path(Path::new("final.txt")).is_ok());
+        assert!(tree.get_path(Path::new("draft.txt")).is_err());
+        assert!(tree.get_path(Path::new("README.md")).is_err());
+
+        // The file renamed away is gone, so deleting it again fails
+        let changes = [
+            FileChange::Rename { from: "final.txt".to_string(), to: "done.txt".to_string() },
+            FileChange::Delete { path: "final.txt".to_string() },
+        ];
+        assert!(perform_changeset(repo_path(&dir), &changes, "Again", &CommitOptions::default()).is_err());
+        assert!(dir.path().join("final.txt").exists());
+    }
+
+    #[test]
+    fn moved_branch_is_a_conflict() {
+        let (dir, repo) = init_repo();
+        let base = head_id(&repo).to_string();
+        perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "First", &CommitOptions::default()).unwrap();
+        let moved_to = head_id(&repo);
+
+        let options = CommitOptions { expected_parent: Some(base.clone()), ..Default::default() };
+        let error = perform_changeset(repo_path(&dir), &[write("b.txt", "b\n")], "Second", &options).unwrap_err();
+        match error.downcast_ref::<CommitError>() {
+            Some(CommitError::Conflict { expected, actual, .. }) => {
+                assert_eq!(expected, &base);
+                assert_eq!(actual, &moved_to.to_string());
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+        assert_eq!(head_id(&repo), moved_to);
+        assert!(!dir.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn unmoved_branch_commits() {
+        let (dir, repo) = init_repo();
+        let options = CommitOptions { expected_parent: Some(head_id(&repo).to_string()), ..Default::default() };
+        let commit_id = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "First", &options).unwrap();
+        assert_eq!(head_id(&repo), commit_id);
+    }
+
+    fn on_branch(branch: &str) -> CommitOptions {
+        CommitOptions { branch: Some(branch.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn branch_not_checked_out_leaves_working_tree_and_index_alone() {
+        let (dir, repo) = init_repo();
+        let head = head_id(&repo);
+        repo.branch("feature", &repo.find_commit(head).unwrap(), false).unwrap();
+        fs::write(dir.path().join("README.md"), "local edit\n").unwrap();
+        let index_tree = repo.index().unwrap().write_tree().unwrap();
+
+        let changes = [write("README.md", "on feature\n"), write("feature.txt", "f\n")];
+        let commit_id = perform_changeset(repo_path(&dir), &changes, "Feature", &on_branch("feature")).unwrap();
+
+        assert_eq!(repo.find_branch("feature", git2::BranchType::Local).unwrap().get().target(), Some(commit_id));
+        assert_eq!(repo.find_commit(commit_id).unwrap().parent_id(0).unwrap(), head);
+        assert_eq!(head_id(&repo), head);
+        assert_eq!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "local edit\n");
+        assert!(!dir.path().join("feature.txt").exists());
+        assert_eq!(repo.index().unwrap().write_tree().unwrap(), index_tree);
+    }
+
+    #[test]
+    fn missing_branch_is_created_from_the_base() {
+        let (dir, repo) = init_repo();
+        let head = head_id(&repo);
+        let options = CommitOptions { create_from: Some("HEAD".to_string()), ..on_branch("topic") };
+        let commit_id = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "Topic", &options).unwrap();
+
+        assert_eq!(repo.find_branch("topic", git2::BranchType::Local).unwrap().get().target(), Some(commit_id));
+        assert_eq!(repo.find_commit(commit_id).unwrap().parent_id(0).unwrap(), head);
+        assert_eq!(head_id(&repo), head);
+        assert!(!dir.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn missing_branch_without_base_is_an_error() {
+        let (dir, repo) = init_repo();
+        let error = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "Topic", &on_branch("topic")).unwrap_err();
+        assert!(error.to_string().contains("does not exist"), "{}", error);
+        assert!(repo.find_branch("topic", git2::BranchType::Local).is_err());
+    }
+
+    #[test]
+    fn invalid_branch_name_is_an_error() {
+        let (dir, repo) = init_repo();
+        let head = head_id(&repo);
+        for branch in ["bad..name", "ends.lock", "has space", "-dash/../x"] {
+            let options = CommitOptions { create_from: Some("HEAD".to_string()), ..on_branch(branch) };
+            let error = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "Bad", &options).unwrap_err();
+            assert!(error.to_string().contains("Invalid branch name"), "{}: {}", branch, error);
+        }
+        assert_eq!(head_id(&repo), head);
+    }
+
+    #[test]
+    fn author_comes_from_the_options_and_committer_from_the_config() {
+        let (dir, repo) = init_repo();
+        let author = Identity { name: "Alice".to_string(), email: "alice@example.com".to_string() };
+        let options = CommitOptions { author: Some(author), ..Default::default() };
+        let commit_id = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "Authored", &options).unwrap();
+        let commit = repo.find_commit(commit_id).unwrap();
+        assert_eq!((commit.author().name(), commit.author().email()), (Some("Alice"), Some("alice@example.com")));
+        assert_eq!((commit.committer().name(), commit.committer().email()), (Some("Test"), Some("test@example.com")));
+
+        // Without an author, the committer is the author too
+        let commit_id = perform_changeset(repo_path(&dir), &[write("b.txt", "b\n")], "Unattributed", &CommitOptions::default()).unwrap();
+        let commit = repo.find_commit(commit_id).unwrap();
+        assert_eq!((commit.author().name(), commit.author().email()), (Some("Test"), Some("test@example.com")));
+    }
+
+    #[test]
+    fn committer_falls_back_without_git_config() {
+        // Hide the global and system config, which may well have a user.name. Every
+        // other test sets its committer in the repository's own config.
+        let empty_config_dir = tempfile::tempdir().unwrap();
+        for level in [git2::ConfigLevel::System, git2::ConfigLevel::XDG, git2::ConfigLevel::Global] {
+            unsafe { git2::opts::set_search_path(level, empty_config_dir.path()).unwrap() };
+        }
+        let (dir, repo) = init_repo();
+        {
+            let mut config = repo.config().unwrap().open_level(git2::ConfigLevel::Local).unwrap();
+            config.remove("user.name").unwrap();
+            config.remove("user.email").unwrap();
+        }
+
+        let author = Identity { name: "Alice".to_string(), email: "alice@example.com".to_string() };
+        let options = CommitOptions { author: Some(author), ..Default::default() };
+        let commit_id = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "Fallback", &options).unwrap();
+        let commit = repo.find_commit(commit_id).unwrap();
+        assert_eq!(commit.author().name(), Some("Alice"));
+        assert_eq!((commit.committer().name(), commit.committer().email()), (Some("Emergency Committer"), Some("emergency@example.com")));
+    }
+
+    /// A bare repository in its own directory, added to `repo` as the remote `origin`.
+    fn add_bare_remote(repo: &Repository) -> (TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let bare = Repository::init_bare(dir.path()).unwrap();
+        repo.remote("origin", dir.path().to_str().unwrap()).unwrap();
+        (dir, bare)
+    }
+
+    #[test]
+    fn push_updates_the_remote_branch() {
+        let (dir, repo) = init_repo();
+        let (_remote_dir, bare) = add_bare_remote(&repo);
+        let commit_id = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "First", &CommitOptions::default()).unwrap();
+        push_branch(repo_path(&dir), "origin", None, &PushCredentials::default()).unwrap();
+
+        let branch = head_branch(repo_path(&dir)).unwrap().unwrap();
+        let remote_id = bare.find_reference(&format!("refs/heads/{}", branch)).unwrap().target().unwrap();
+        assert_eq!(remote_id, commit_id);
+    }
+
+    #[test]
+    fn non_fast_forward_push_is_rejected() {
+        let (dir, repo) = init_repo();
+        let (_remote_dir, bare) = add_bare_remote(&repo);
+        push_branch(repo_path(&dir), "origin", None, &PushCredentials::default()).unwrap();
+
+        // Someone else pushes first, so the remote has a commit we don't
+        let branch = head_branch(repo_path(&dir)).unwrap().unwrap();
+        let ref_name = format!("refs/heads/{}", branch);
+        let remote_base = bare.find_reference(&ref_name).unwrap().peel_to_commit().unwrap();
+        let signature = Signature::now("Other", "other@example.com").unwrap();
+        let other_id = bare
+            .commit(Some(&ref_name), &signature, &signature, "Elsewhere", &remote_base.tree().unwrap(), &[&remote_base])
+            .unwrap();
+
+        perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "Diverging", &CommitOptions::default()).unwrap();
+        assert!(push_branch(repo_path(&dir), "origin", None, &PushCredentials::default()).is_err());
+        assert_eq!(bare.find_reference(&ref_name).unwrap().target().unwrap(), other_id);
+    }
+
+    /// `init_repo` plus a commit holding `config/env/prod` and `config/env/dev`.
+    fn init_repo_with_directory() -> (TempDir, Repository) {
+        let (dir, repo) = init_repo();
+        let changes = [write("config/env/prod", "prod\n"), write("config/env/dev", "dev\n")];
+        perform_changeset(repo_path(&dir), &changes, "Add config", &CommitOptions::default()).unwrap();
+        (dir, repo)
+    }
+
+    fn in_memory() -> CommitOptions {
+        CommitOptions { in_memory: true, ..Default::default() }
+    }
+
+    #[test]
+    fn in_memory_commit_leaves_working_tree_alone() {
+        let (dir, repo) = init_repo();
+        fs::write(dir.path().join("README.md"), "local edit\n").unwrap();
+        let commit_id = perform_changeset(repo_path(&dir), &[write("a.txt", "a\n")], "Memory", &in_memory()).unwrap();
+        assert_eq!(head_id(&repo), commit_id);
+        assert!(repo.find_commit(commit_id).unwrap().tree().unwrap().get_path(Path::new("a.txt")).is_ok());
+        assert!(!dir.path().join("a.txt").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "local edit\n");
+    }
+
+    #[test]
+    fn in_memory_file_cannot_replace_a_directory() {
+        let (dir, repo) = init_repo_with_directory();
+        let head = head_id(&repo);
+        assert!(perform_changeset(repo_path(&dir), &[write("config/env", "x\n")], "Clash", &in_memory()).is_err());
+        let rename = FileChange::Rename { from: "README.md".to_string(), to: "config".to_string() };
+        assert!(perform_changeset(repo_path(&dir), &[rename], "Clash", &in_memory()).is_err());
+        assert_eq!(head_id(&repo), head);
+    }
+
+    #[test]
+    fn in_memory_file_cannot_go_below_a_file() {
+        let (dir, repo) = init_repo_with_directory();
+        let head = head_id(&repo);
+        assert!(perform_changeset(repo_path(&dir), &[write("README.md/x", "x\n")], "Clash", &in_memory()).is_err());
+        let rename = FileChange::Rename { from: "README.md".to_string(), to: "config/env/prod/x".to_string() };
+        assert!(perform_changeset(repo_path(&dir), &[rename], "Clash", &in_memory()).is_err());
+        // Nor below a file written earlier in the same changeset
+        let changes = [write("notes", "n\n"), write("notes/today", "t\n")];
+        assert!(perform_changeset(repo_path(&dir), &changes, "Clash", &in_memory()).is_err());
+        assert_eq!(head_id(&repo), head);
+    }
+
+    #[test]
+    fn in_memory_directory_can_be_replaced_once_emptied() {
+        let (dir, repo) = init_repo_with_directory();
+        let changes = [
+            FileChange::Delete { path: "config/env/prod".to_string() },
+            FileChange::Delete { path: "config/env/dev".to_string() },
+            write("config/env", "flat\n"),
+        ];
+        let commit_id = perform_changeset(repo_path(&dir), &changes, "Flatten", &in_memory()).unwrap();
+        let tree = repo.find_commit(commit_id).unwrap().tree().unwrap();
+        let entry = tree.get_path(Path::new("config/env")).unwrap();
+        assert_eq!(entry.kind(), Some(git2::ObjectType::Blob));
+    }
+
+    #[test]
+    fn working_tree_file_cannot_clash_with_a_directory() {
+        let (dir, repo) = init_repo_with_directory();
+        let head = head_id(&repo);
+        let options = CommitOptions::default();
+        assert!(perform_changeset(repo_path(&dir), &[write("config/env", "x\n")], "Clash", &options).is_err());
+        let changes = [write("a.txt", "a\n"), write("README.md/x", "x\n")];
+        assert!(perform_changeset(repo_path(&dir), &changes, "Clash", &options).is_err());
+        assert!(!dir.path().join("a.txt").exists());
+        assert_eq!(head_id(&repo), head);
+    }
+
+    #[test]
+    fn bare_repository_commits_in_memory() {
+        let (dir, repo) = init_repo_with_directory();
+        let (bare_dir, bare) = add_bare_remote(&repo);
+        push_branch(repo_path(&dir), "origin", None, &PushCredentials::default()).unwrap();
+        let branch = head_branch(repo_path(&dir)).unwrap().unwrap();
+        bare.set_head(&format!("refs/heads/{}", branch)).unwrap();
+        let bare_path = bare_dir.path().to_str().unwrap();
+
+        let commit_id = perform_changeset(bare_path, &[write("a.txt", "a\n")], "Bare", &CommitOptions::default()).unwrap();
+        assert_eq!(head_id(&bare), commit_id);
+        assert!(bare.find_commit(commit_id).unwrap().tree().unwrap().get_path(Path::new("a.txt")).is_ok());
+
+        assert!(perform_changeset(bare_path, &[write("config", "x\n")], "Clash", &CommitOptions::default()).is_err());
+        assert!(perform_changeset(bare_path, &[write("a.txt/b", "x\n")], "Clash", &CommitOptions::default()).is_err());
+        assert_eq!(head_id(&bare), commit_id);
+    }
+
+    fn invalid_path_reason(path: &str) -> Option<&'static str> {
+        match validate_path(path) {
+            Err(CommitError::InvalidPath { reason, .. }) => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn paths_outside_the_repository_are_rejected() {
+        assert_eq!(invalid_path_reason("../x"), Some("path leaves the repository"));
+        assert_eq!(invalid_path_reason("docs/../../x"), Some("path leaves the repository"));
+        assert_eq!(invalid_path_reason("/etc/passwd"), Some("path is absolute"));
+        assert_eq!(invalid_path_reason("./x"), Some("path contains '.'"));
+        assert_eq!(invalid_path_reason(""), Some("path is empty"));
+    }
+
+    #[test]
+    fn git_directory_is_rejected_in_any_case() {
+        assert_eq!(invalid_path_reason(".git/config"), Some("path enters the .git directory"));
+        assert_eq!(invalid_path_reason(".GIT/hooks/x"), Some("path enters the .git directory"));
+        assert_eq!(invalid_path_reason("sub/.Git/config"), Some("path enters the .git directory"));
+    }
+
+    #[test]
+    fn ordinary_paths_are_accepted() {
+        assert_eq!(invalid_path_reason("README.md"), None);
+        assert_eq!(invalid_path_reason("docs/notes/today.md"), None);
+        assert_eq!(invalid_path_reason(".github/workflows/ci.yml"), None);
+        assert_eq!(invalid_path_reason("a..b"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directory_leading_outside_is_rejected() {
+        let (dir, repo) = init_repo();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("escape")).unwrap();
+        let head = head_id(&repo);
+
+        let workdir = fs::canonicalize(dir.path()).unwrap();
+        assert!(path_in_workdir(&workdir, "escape/x.txt").is_err());
+        assert!(path_in_workdir(&workdir, "escape/new/dir/x.txt").is_err());
+        assert!(path_in_workdir(&workdir, "inside/x.txt").is_ok());
+
+        let error = perform_changeset(repo_path(&dir), &[write("escape/x.txt", "x\n")], "Escape", &CommitOptions::default())
+            .unwrap_err();
+        assert!(matches!(error.downcast_ref::<CommitError>(), Some(CommitError::InvalidPath { .. })));
+        assert!(!outside.path().join("x.txt").exists());
+        assert_eq!(head_id(&repo), head);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_through_symlinked_file_is_rejected() {
+        let (dir, repo) = init_repo();
+        let outside = tempfile::tempdir().unwrap();
+        let target = outside.path().join("secret.txt");
+        fs::write(&target, "original\n").unwrap();
+        std::os::unix::fs::symlink(&target, dir.path().join("link.txt")).unwrap();
+        let head = head_id(&repo);
+
+        let error = perform_changeset(repo_path(&dir), &[write("link.txt", "overwritten\n")], "Through", &CommitOptions::default())
+            .unwrap_err();
+        assert!(matches!(error.downcast_ref::<CommitError>(), Some(CommitError::InvalidPath { .. })));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "original\n");
+        assert_eq!(head_id(&repo), head);
+    }
+}
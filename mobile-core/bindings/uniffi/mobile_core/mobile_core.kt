@@ -387,252 +387,271 @@ private inline fun <reified Lib : Library> loadIndirect(
 internal interface UniffiRustFutureContinuationCallback : com.sun.jna.Callback {
     fun callback(`data`: Long,`pollResult`: Byte,)
 }
-internal interface UniffiForeignFutureDroppedCallback : com.sun.jna.Callback {
+internal interface UniffiForeignFutureFree : com.sun.jna.Callback {
     fun callback(`handle`: Long,)
 }
 internal interface UniffiCallbackInterfaceFree : com.sun.jna.Callback {
     fun callback(`handle`: Long,)
 }
 @Structure.FieldOrder("handle", "free")
-internal open class UniffiForeignFutureDroppedCallbackStruct(
+internal open class UniffiForeignFuture(
     @JvmField internal var `handle`: Long = 0.toLong(),
-    @JvmField internal var `free`: UniffiForeignFutureDroppedCallback? = null,
+    @JvmField internal var `free`: UniffiForeignFutureFree? = null,
 ) : Structure() {
     class UniffiByValue(
         `handle`: Long = 0.toLong(),
-        `free`: UniffiForeignFutureDroppedCallback? = null,
-    ): UniffiForeignFutureDroppedCallbackStruct(`handle`,`free`,), Structure.ByValue
+        `free`: UniffiForeignFutureFree? = null,
+    ): UniffiForeignFuture(`handle`,`free`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureDroppedCallbackStruct) {
+   internal fun uniffiSetValue(other: UniffiForeignFuture) {
         `handle` = other.`handle`
         `free` = other.`free`
     }
 
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultU8(
+internal open class UniffiForeignFutureStructU8(
     @JvmField internal var `returnValue`: Byte = 0.toByte(),
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Byte = 0.toByte(),
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultU8(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructU8(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultU8) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructU8) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteU8 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultU8.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructU8.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultI8(
+internal open class UniffiForeignFutureStructI8(
     @JvmField internal var `returnValue`: Byte = 0.toByte(),
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Byte = 0.toByte(),
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultI8(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructI8(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultI8) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructI8) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteI8 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultI8.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructI8.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultU16(
+internal open class UniffiForeignFutureStructU16(
     @JvmField internal var `returnValue`: Short = 0.toShort(),
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Short = 0.toShort(),
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultU16(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructU16(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultU16) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructU16) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteU16 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultU16.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructU16.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultI16(
+internal open class UniffiForeignFutureStructI16(
     @JvmField internal var `returnValue`: Short = 0.toShort(),
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Short = 0.toShort(),
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultI16(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructI16(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultI16) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructI16) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteI16 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultI16.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructI16.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultU32(
+internal open class UniffiForeignFutureStructU32(
     @JvmField internal var `returnValue`: Int = 0,
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Int = 0,
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultU32(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructU32(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultU32) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructU32) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteU32 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultU32.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructU32.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultI32(
+internal open class UniffiForeignFutureStructI32(
     @JvmField internal var `returnValue`: Int = 0,
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Int = 0,
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultI32(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructI32(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultI32) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructI32) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteI32 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultI32.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructI32.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultU64(
+internal open class UniffiForeignFutureStructU64(
     @JvmField internal var `returnValue`: Long = 0.toLong(),
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Long = 0.toLong(),
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultU64(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructU64(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultU64) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructU64) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteU64 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultU64.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructU64.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultI64(
+internal open class UniffiForeignFutureStructI64(
     @JvmField internal var `returnValue`: Long = 0.toLong(),
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Long = 0.toLong(),
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultI64(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructI64(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultI64) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructI64) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteI64 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultI64.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructI64.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultF32(
+internal open class UniffiForeignFutureStructF32(
     @JvmField internal var `returnValue`: Float = 0.0f,
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Float = 0.0f,
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultF32(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructF32(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultF32) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructF32) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteF32 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultF32.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructF32.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultF64(
+internal open class UniffiForeignFutureStructF64(
     @JvmField internal var `returnValue`: Double = 0.0,
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: Double = 0.0,
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultF64(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructF64(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultF64) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructF64) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteF64 : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultF64.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructF64.UniffiByValue,)
 }
 @Structure.FieldOrder("returnValue", "callStatus")
-internal open class UniffiForeignFutureResultRustBuffer(
+internal open class UniffiForeignFutureStructPointer(
+    @JvmField internal var `returnValue`: Pointer = Pointer.NULL,
+    @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
+) : Structure() {
+    class UniffiByValue(
+        `returnValue`: Pointer = Pointer.NULL,
+        `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
+    ): UniffiForeignFutureStructPointer(`returnValue`,`callStatus`,), Structure.ByValue
+
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructPointer) {
+        `returnValue` = other.`returnValue`
+        `callStatus` = other.`callStatus`
+    }
+
+}
+internal interface UniffiForeignFutureCompletePointer : com.sun.jna.Callback {
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructPointer.UniffiByValue,)
+}
+@Structure.FieldOrder("returnValue", "callStatus")
+internal open class UniffiForeignFutureStructRustBuffer(
     @JvmField internal var `returnValue`: RustBuffer.ByValue = RustBuffer.ByValue(),
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `returnValue`: RustBuffer.ByValue = RustBuffer.ByValue(),
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultRustBuffer(`returnValue`,`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructRustBuffer(`returnValue`,`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultRustBuffer) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructRustBuffer) {
         `returnValue` = other.`returnValue`
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteRustBuffer : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultRustBuffer.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructRustBuffer.UniffiByValue,)
 }
 @Structure.FieldOrder("callStatus")
-internal open class UniffiForeignFutureResultVoid(
+internal open class UniffiForeignFutureStructVoid(
     @JvmField internal var `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
 ) : Structure() {
     class UniffiByValue(
         `callStatus`: UniffiRustCallStatus.ByValue = UniffiRustCallStatus.ByValue(),
-    ): UniffiForeignFutureResultVoid(`callStatus`,), Structure.ByValue
+    ): UniffiForeignFutureStructVoid(`callStatus`,), Structure.ByValue
 
-   internal fun uniffiSetValue(other: UniffiForeignFutureResultVoid) {
+   internal fun uniffiSetValue(other: UniffiForeignFutureStructVoid) {
         `callStatus` = other.`callStatus`
     }
 
 }
 internal interface UniffiForeignFutureCompleteVoid : com.sun.jna.Callback {
-    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureResultVoid.UniffiByValue,)
+    fun callback(`callbackData`: Long,`result`: UniffiForeignFutureStructVoid.UniffiByValue,)
 }
 
 
@@ -679,6 +698,18 @@ internal interface UniffiForeignFutureCompleteVoid : com.sun.jna.Callback {
 
 
 
+
+
+
+
+
+
+
+
+
+
+
+
 
 
 
@@ -709,8 +740,16 @@ internal interface IntegrityCheckingUniffiLib : Library {
     // Integrity check functions only
     fun uniffi_mobile_core_checksum_func_emergency_commit(
 ): Short
+fun uniffi_mobile_core_checksum_func_emergency_commit_files(
+): Short
+fun uniffi_mobile_core_checksum_func_list_repos(
+): Short
 fun uniffi_mobile_core_checksum_func_pair(
 ): Short
+fun uniffi_mobile_core_checksum_func_parse_pairing_uri(
+): Short
+fun uniffi_mobile_core_checksum_func_set_network_options(
+): Short
 fun ffi_mobile_core_uniffi_contract_version(
 ): Int
 
@@ -756,9 +795,17 @@ internal interface UniffiLib : Library {
     }
 
     // FFI functions
-    fun uniffi_mobile_core_fn_func_emergency_commit(`dataDir`: RustBuffer.ByValue,`daemonFullAddr`: RustBuffer.ByValue,`repoPath`: RustBuffer.ByValue,`filePath`: RustBuffer.ByValue,`newContent`: RustBuffer.ByValue,`commitMessage`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    fun uniffi_mobile_core_fn_func_emergency_commit(`dataDir`: RustBuffer.ByValue,`daemonFullAddr`: RustBuffer.ByValue,`repo`: RustBuffer.ByValue,`filePath`: RustBuffer.ByValue,`newContent`: RustBuffer.ByValue,`commitMessage`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+): RustBuffer.ByValue
+fun uniffi_mobile_core_fn_func_emergency_commit_files(`dataDir`: RustBuffer.ByValue,`daemonFullAddr`: RustBuffer.ByValue,`repo`: RustBuffer.ByValue,`changes`: RustBuffer.ByValue,`commitMessage`: RustBuffer.ByValue,`options`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
 ): RustBuffer.ByValue
-fun uniffi_mobile_core_fn_func_pair(`dataDir`: RustBuffer.ByValue,`daemonFullAddr`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+fun uniffi_mobile_core_fn_func_list_repos(`dataDir`: RustBuffer.ByValue,`daemonFullAddr`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+): RustBuffer.ByValue
+fun uniffi_mobile_core_fn_func_pair(`dataDir`: RustBuffer.ByValue,`daemonFullAddr`: RustBuffer.ByValue,`code`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+): Unit
+fun uniffi_mobile_core_fn_func_parse_pairing_uri(`uri`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+): RustBuffer.ByValue
+fun uniffi_mobile_core_fn_func_set_network_options(`options`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
 ): Unit
 fun ffi_mobile_core_rustbuffer_alloc(`size`: Long,uniffi_out_err: UniffiRustCallStatus, 
 ): RustBuffer.ByValue
@@ -848,6 +895,14 @@ fun ffi_mobile_core_rust_future_free_f64(`handle`: Long,
 ): Unit
 fun ffi_mobile_core_rust_future_complete_f64(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
 ): Double
+fun ffi_mobile_core_rust_future_poll_pointer(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
+): Unit
+fun ffi_mobile_core_rust_future_cancel_pointer(`handle`: Long,
+): Unit
+fun ffi_mobile_core_rust_future_free_pointer(`handle`: Long,
+): Unit
+fun ffi_mobile_core_rust_future_complete_pointer(`handle`: Long,uniffi_out_err: UniffiRustCallStatus, 
+): Pointer
 fun ffi_mobile_core_rust_future_poll_rust_buffer(`handle`: Long,`callback`: UniffiRustFutureContinuationCallback,`callbackData`: Long,
 ): Unit
 fun ffi_mobile_core_rust_future_cancel_rust_buffer(`handle`: Long,
@@ -878,10 +933,22 @@ private fun uniffiCheckContractApiVersion(lib: IntegrityCheckingUniffiLib) {
 }
 @Suppress("UNUSED_PARAMETER")
 private fun uniffiCheckApiChecksums(lib: IntegrityCheckingUniffiLib) {
-    if (lib.uniffi_mobile_core_checksum_func_emergency_commit() != 5551.toShort()) {
+    if (lib.uniffi_mobile_core_checksum_func_emergency_commit() != 65127.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_mobile_core_checksum_func_emergency_commit_files() != 38633.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_mobile_core_checksum_func_list_repos() != 51654.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_mobile_core_checksum_func_pair() != 59910.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_mobile_core_checksum_func_parse_pairing_uri() != 11406.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
-    if (lib.uniffi_mobile_core_checksum_func_pair() != 58111.toShort()) {
+    if (lib.uniffi_mobile_core_checksum_func_set_network_options() != 3674.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
 }
@@ -957,22 +1024,34 @@ inline fun <T : Disposable?, R> T.use(block: (T) -> R) =
     }
 
 /** 
- * Placeholder object used to signal that we're constructing an interface with a FFI handle.
- *
- * This is the first argument for interface constructors that input a raw handle. It exists is that
- * so we can avoid signature conflicts when an interface has a regular constructor than inputs a
- * Long.
+ * Used to instantiate an interface without an actual pointer, for fakes in tests, mostly.
  *
  * @suppress
  * */
-object UniffiWithHandle
+object NoPointer
 
-/** 
- * Used to instantiate an interface without an actual pointer, for fakes in tests, mostly.
- *
+/**
  * @suppress
- * */
-object NoHandle
+ */
+public object FfiConverterBoolean: FfiConverter<Boolean, Byte> {
+    override fun lift(value: Byte): Boolean {
+        return value.toInt() != 0
+    }
+
+    override fun read(buf: ByteBuffer): Boolean {
+        return lift(buf.get())
+    }
+
+    override fun lower(value: Boolean): Byte {
+        return if (value) 1.toByte() else 0.toByte()
+    }
+
+    override fun allocationSize(value: Boolean) = 1UL
+
+    override fun write(value: Boolean, buf: ByteBuffer) {
+        buf.put(lower(value))
+    }
+}
 
 /**
  * @suppress
@@ -1033,22 +1112,240 @@ public object FfiConverterString: FfiConverter<String, RustBuffer.ByValue> {
 
 
 
+data class CommitOptions (
+    var `branch`: kotlin.String? = null, 
+    var `createBranchFrom`: kotlin.String? = null, 
+    var `expectedParent`: kotlin.String? = null, 
+    var `push`: kotlin.Boolean = false
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeCommitOptions: FfiConverterRustBuffer<CommitOptions> {
+    override fun read(buf: ByteBuffer): CommitOptions {
+        return CommitOptions(
+            FfiConverterOptionalString.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterBoolean.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: CommitOptions) = (
+            FfiConverterOptionalString.allocationSize(value.`branch`) +
+            FfiConverterOptionalString.allocationSize(value.`createBranchFrom`) +
+            FfiConverterOptionalString.allocationSize(value.`expectedParent`) +
+            FfiConverterBoolean.allocationSize(value.`push`)
+    )
+
+    override fun write(value: CommitOptions, buf: ByteBuffer) {
+            FfiConverterOptionalString.write(value.`branch`, buf)
+            FfiConverterOptionalString.write(value.`createBranchFrom`, buf)
+            FfiConverterOptionalString.write(value.`expectedParent`, buf)
+            FfiConverterBoolean.write(value.`push`, buf)
+    }
+}
 
 
-sealed class CoreException: kotlin.Exception() {
+
+data class CommitOutcome (
+    var `commitHash`: kotlin.String, 
+    var `pushResult`: PushResult?
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeCommitOutcome: FfiConverterRustBuffer<CommitOutcome> {
+    override fun read(buf: ByteBuffer): CommitOutcome {
+        return CommitOutcome(
+            FfiConverterString.read(buf),
+            FfiConverterOptionalTypePushResult.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: CommitOutcome) = (
+            FfiConverterString.allocationSize(value.`commitHash`) +
+            FfiConverterOptionalTypePushResult.allocationSize(value.`pushResult`)
+    )
+
+    override fun write(value: CommitOutcome, buf: ByteBuffer) {
+            FfiConverterString.write(value.`commitHash`, buf)
+            FfiConverterOptionalTypePushResult.write(value.`pushResult`, buf)
+    }
+}
+
+
+
+data class NetworkOptions (
+    var `privateNetwork`: kotlin.Boolean = false, 
+    var `bootstrapNodes`: List<kotlin.String>? = null, 
+    var `preSharedKey`: kotlin.String? = null
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeNetworkOptions: FfiConverterRustBuffer<NetworkOptions> {
+    override fun read(buf: ByteBuffer): NetworkOptions {
+        return NetworkOptions(
+            FfiConverterBoolean.read(buf),
+            FfiConverterOptionalSequenceString.read(buf),
+            FfiConverterOptionalString.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: NetworkOptions) = (
+            FfiConverterBoolean.allocationSize(value.`privateNetwork`) +
+            FfiConverterOptionalSequenceString.allocationSize(value.`bootstrapNodes`) +
+            FfiConverterOptionalString.allocationSize(value.`preSharedKey`)
+    )
+
+    override fun write(value: NetworkOptions, buf: ByteBuffer) {
+            FfiConverterBoolean.write(value.`privateNetwork`, buf)
+            FfiConverterOptionalSequenceString.write(value.`bootstrapNodes`, buf)
+            FfiConverterOptionalString.write(value.`preSharedKey`, buf)
+    }
+}
+
+
+
+data class PairingInfo (
+    var `peerId`: kotlin.String, 
+    var `addresses`: List<kotlin.String>, 
+    var `code`: kotlin.String, 
+    var `preSharedKey`: kotlin.String?
+) {
     
-     class NetworkException(
-        // The 'override' keyword is added here as well.
-        override val `message`: kotlin.String
-    ) : CoreException() {
-        // The `get()` part is removed from here too.
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypePairingInfo: FfiConverterRustBuffer<PairingInfo> {
+    override fun read(buf: ByteBuffer): PairingInfo {
+        return PairingInfo(
+            FfiConverterString.read(buf),
+            FfiConverterSequenceString.read(buf),
+            FfiConverterString.read(buf),
+            FfiConverterOptionalString.read(buf),
+        )
     }
 
+    override fun allocationSize(value: PairingInfo) = (
+            FfiConverterString.allocationSize(value.`peerId`) +
+            FfiConverterSequenceString.allocationSize(value.`addresses`) +
+            FfiConverterString.allocationSize(value.`code`) +
+            FfiConverterOptionalString.allocationSize(value.`preSharedKey`)
+    )
+
+    override fun write(value: PairingInfo, buf: ByteBuffer) {
+            FfiConverterString.write(value.`peerId`, buf)
+            FfiConverterSequenceString.write(value.`addresses`, buf)
+            FfiConverterString.write(value.`code`, buf)
+            FfiConverterOptionalString.write(value.`preSharedKey`, buf)
+    }
+}
+
+
+
+data class PushResult (
+    var `success`: kotlin.Boolean, 
+    var `errorMessage`: kotlin.String?
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypePushResult: FfiConverterRustBuffer<PushResult> {
+    override fun read(buf: ByteBuffer): PushResult {
+        return PushResult(
+            FfiConverterBoolean.read(buf),
+            FfiConverterOptionalString.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: PushResult) = (
+            FfiConverterBoolean.allocationSize(value.`success`) +
+            FfiConverterOptionalString.allocationSize(value.`errorMessage`)
+    )
+
+    override fun write(value: PushResult, buf: ByteBuffer) {
+            FfiConverterBoolean.write(value.`success`, buf)
+            FfiConverterOptionalString.write(value.`errorMessage`, buf)
+    }
+}
+
+
+
+data class RepoInfo (
+    var `alias`: kotlin.String, 
+    var `defaultBranch`: kotlin.String?, 
+    var `canPush`: kotlin.Boolean
+) {
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeRepoInfo: FfiConverterRustBuffer<RepoInfo> {
+    override fun read(buf: ByteBuffer): RepoInfo {
+        return RepoInfo(
+            FfiConverterString.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterBoolean.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: RepoInfo) = (
+            FfiConverterString.allocationSize(value.`alias`) +
+            FfiConverterOptionalString.allocationSize(value.`defaultBranch`) +
+            FfiConverterBoolean.allocationSize(value.`canPush`)
+    )
+
+    override fun write(value: RepoInfo, buf: ByteBuffer) {
+            FfiConverterString.write(value.`alias`, buf)
+            FfiConverterOptionalString.write(value.`defaultBranch`, buf)
+            FfiConverterBoolean.write(value.`canPush`, buf)
+    }
+}
+
+
+
+
+
+sealed class CoreException: kotlin.Exception() {
+    
+    class NetworkException(
+        
+        val `message`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "message=${ `message` }"
+    }
+    
     class JsonException(
-        // The 'override' keyword is added here as well.
-        override val `message`: kotlin.String
-    ) : CoreException() {
-        // The `get()` part is removed from here too.
+        
+        val `message`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "message=${ `message` }"
     }
     
     class Timeout(
@@ -1057,6 +1354,46 @@ sealed class CoreException: kotlin.Exception() {
             get() = ""
     }
     
+    class Conflict(
+        
+        val `message`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "message=${ `message` }"
+    }
+    
+    class InvalidPath(
+        
+        val `message`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "message=${ `message` }"
+    }
+    
+    class Forbidden(
+        
+        val `message`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "message=${ `message` }"
+    }
+    
+    class IncompatibleDaemon(
+        
+        val `message`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "message=${ `message` }"
+    }
+    
+    class InvalidPairingUri(
+        
+        val `message`: kotlin.String
+        ) : CoreException() {
+        override val message
+            get() = "message=${ `message` }"
+    }
+    
 
     companion object ErrorHandler : UniffiRustCallStatusErrorHandler<CoreException> {
         override fun lift(error_buf: RustBuffer.ByValue): CoreException = FfiConverterTypeCoreError.lift(error_buf)
@@ -1080,6 +1417,21 @@ public object FfiConverterTypeCoreError : FfiConverterRustBuffer<CoreException>
                 FfiConverterString.read(buf),
                 )
             3 -> CoreException.Timeout()
+            4 -> CoreException.Conflict(
+                FfiConverterString.read(buf),
+                )
+            5 -> CoreException.InvalidPath(
+                FfiConverterString.read(buf),
+                )
+            6 -> CoreException.Forbidden(
+                FfiConverterString.read(buf),
+                )
+            7 -> CoreException.IncompatibleDaemon(
+                FfiConverterString.read(buf),
+                )
+            8 -> CoreException.InvalidPairingUri(
+                FfiConverterString.read(buf),
+                )
             else -> throw RuntimeException("invalid error enum value, something is very wrong!!")
         }
     }
@@ -1100,6 +1452,31 @@ public object FfiConverterTypeCoreError : FfiConverterRustBuffer<CoreException>
                 // Add the size for the Int that specifies the variant plus the size needed for all fields
                 4UL
             )
+            is CoreException.Conflict -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`message`)
+            )
+            is CoreException.InvalidPath -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`message`)
+            )
+            is CoreException.Forbidden -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`message`)
+            )
+            is CoreException.IncompatibleDaemon -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`message`)
+            )
+            is CoreException.InvalidPairingUri -> (
+                // Add the size for the Int that specifies the variant plus the size needed for all fields
+                4UL
+                + FfiConverterString.allocationSize(value.`message`)
+            )
         }
     }
 
@@ -1119,25 +1496,367 @@ public object FfiConverterTypeCoreError : FfiConverterRustBuffer<CoreException>
                 buf.putInt(3)
                 Unit
             }
+            is CoreException.Conflict -> {
+                buf.putInt(4)
+                FfiConverterString.write(value.`message`, buf)
+                Unit
+            }
+            is CoreException.InvalidPath -> {
+                buf.putInt(5)
+                FfiConverterString.write(value.`message`, buf)
+                Unit
+            }
+            is CoreException.Forbidden -> {
+                buf.putInt(6)
+                FfiConverterString.write(value.`message`, buf)
+                Unit
+            }
+            is CoreException.IncompatibleDaemon -> {
+                buf.putInt(7)
+                FfiConverterString.write(value.`message`, buf)
+                Unit
+            }
+            is CoreException.InvalidPairingUri -> {
+                buf.putInt(8)
+                FfiConverterString.write(value.`message`, buf)
+                Unit
+            }
         }.let { /* this makes the `when` an expression, which ensures it is exhaustive */ }
     }
 
 }
-    @Throws(CoreException::class) fun `emergencyCommit`(`dataDir`: kotlin.String, `daemonFullAddr`: kotlin.String, `repoPath`: kotlin.String, `filePath`: kotlin.String, `newContent`: kotlin.String, `commitMessage`: kotlin.String): kotlin.String {
+
+
+
+sealed class FileChange {
+    
+    data class Write(
+        val `path`: kotlin.String, 
+        val `content`: kotlin.String) : FileChange() {
+        companion object
+    }
+    
+    data class Delete(
+        val `path`: kotlin.String) : FileChange() {
+        companion object
+    }
+    
+    data class Rename(
+        val `from`: kotlin.String, 
+        val `to`: kotlin.String) : FileChange() {
+        companion object
+    }
+    
+
+    
+    companion object
+}
+
+/**
+ * @suppress
+ */
+public object FfiConverterTypeFileChange : FfiConverterRustBuffer<FileChange>{
+    override fun read(buf: ByteBuffer): FileChange {
+        return when(buf.getInt()) {
+            1 -> FileChange.Write(
+                FfiConverterString.read(buf),
+                FfiConverterString.read(buf),
+                )
+            2 -> FileChange.Delete(
+                FfiConverterString.read(buf),
+                )
+            3 -> FileChange.Rename(
+                FfiConverterString.read(buf),
+                FfiConverterString.read(buf),
+                )
+            else -> throw RuntimeException("invalid enum value, something is very wrong!!")
+        }
+    }
+
+    override fun allocationSize(value: FileChange) = when(value) {
+        is FileChange.Write -> {
+            // Add the size for the Int that specifies the variant plus the size needed for all fields
+            (
+                4UL
+                + FfiConverterString.allocationSize(value.`path`)
+                + FfiConverterString.allocationSize(value.`content`)
+            )
+        }
+        is FileChange.Delete -> {
+            // Add the size for the Int that specifies the variant plus the size needed for all fields
+            (
+                4UL
+                + FfiConverterString.allocationSize(value.`path`)
+            )
+        }
+        is FileChange.Rename -> {
+            // Add the size for the Int that specifies the variant plus the size needed for all fields
+            (
+                4UL
+                + FfiConverterString.allocationSize(value.`from`)
+                + FfiConverterString.allocationSize(value.`to`)
+            )
+        }
+    }
+
+    override fun write(value: FileChange, buf: ByteBuffer) {
+        when(value) {
+            is FileChange.Write -> {
+                buf.putInt(1)
+                FfiConverterString.write(value.`path`, buf)
+                FfiConverterString.write(value.`content`, buf)
+                Unit
+            }
+            is FileChange.Delete -> {
+                buf.putInt(2)
+                FfiConverterString.write(value.`path`, buf)
+                Unit
+            }
+            is FileChange.Rename -> {
+                buf.putInt(3)
+                FfiConverterString.write(value.`from`, buf)
+                FfiConverterString.write(value.`to`, buf)
+                Unit
+            }
+        }.let { /* this makes the `when` an expression, which ensures it is exhaustive */ }
+    }
+}
+
+
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterOptionalString: FfiConverterRustBuffer<kotlin.String?> {
+    override fun read(buf: ByteBuffer): kotlin.String? {
+        if (buf.get().toInt() == 0) {
+            return null
+        }
+        return FfiConverterString.read(buf)
+    }
+
+    override fun allocationSize(value: kotlin.String?): ULong {
+        if (value == null) {
+            return 1UL
+        } else {
+            return 1UL + FfiConverterString.allocationSize(value)
+        }
+    }
+
+    override fun write(value: kotlin.String?, buf: ByteBuffer) {
+        if (value == null) {
+            buf.put(0)
+        } else {
+            buf.put(1)
+            FfiConverterString.write(value, buf)
+        }
+    }
+}
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterOptionalTypePushResult: FfiConverterRustBuffer<PushResult?> {
+    override fun read(buf: ByteBuffer): PushResult? {
+        if (buf.get().toInt() == 0) {
+            return null
+        }
+        return FfiConverterTypePushResult.read(buf)
+    }
+
+    override fun allocationSize(value: PushResult?): ULong {
+        if (value == null) {
+            return 1UL
+        } else {
+            return 1UL + FfiConverterTypePushResult.allocationSize(value)
+        }
+    }
+
+    override fun write(value: PushResult?, buf: ByteBuffer) {
+        if (value == null) {
+            buf.put(0)
+        } else {
+            buf.put(1)
+            FfiConverterTypePushResult.write(value, buf)
+        }
+    }
+}
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterOptionalSequenceString: FfiConverterRustBuffer<List<kotlin.String>?> {
+    override fun read(buf: ByteBuffer): List<kotlin.String>? {
+        if (buf.get().toInt() == 0) {
+            return null
+        }
+        return FfiConverterSequenceString.read(buf)
+    }
+
+    override fun allocationSize(value: List<kotlin.String>?): ULong {
+        if (value == null) {
+            return 1UL
+        } else {
+            return 1UL + FfiConverterSequenceString.allocationSize(value)
+        }
+    }
+
+    override fun write(value: List<kotlin.String>?, buf: ByteBuffer) {
+        if (value == null) {
+            buf.put(0)
+        } else {
+            buf.put(1)
+            FfiConverterSequenceString.write(value, buf)
+        }
+    }
+}
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterSequenceString: FfiConverterRustBuffer<List<kotlin.String>> {
+    override fun read(buf: ByteBuffer): List<kotlin.String> {
+        val len = buf.getInt()
+        return List<kotlin.String>(len) {
+            FfiConverterString.read(buf)
+        }
+    }
+
+    override fun allocationSize(value: List<kotlin.String>): ULong {
+        val sizeForLength = 4UL
+        val sizeForItems = value.map { FfiConverterString.allocationSize(it) }.sum()
+        return sizeForLength + sizeForItems
+    }
+
+    override fun write(value: List<kotlin.String>, buf: ByteBuffer) {
+        buf.putInt(value.size)
+        value.iterator().forEach {
+            FfiConverterString.write(it, buf)
+        }
+    }
+}
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterSequenceTypeRepoInfo: FfiConverterRustBuffer<List<RepoInfo>> {
+    override fun read(buf: ByteBuffer): List<RepoInfo> {
+        val len = buf.getInt()
+        return List<RepoInfo>(len) {
+            FfiConverterTypeRepoInfo.read(buf)
+        }
+    }
+
+    override fun allocationSize(value: List<RepoInfo>): ULong {
+        val sizeForLength = 4UL
+        val sizeForItems = value.map { FfiConverterTypeRepoInfo.allocationSize(it) }.sum()
+        return sizeForLength + sizeForItems
+    }
+
+    override fun write(value: List<RepoInfo>, buf: ByteBuffer) {
+        buf.putInt(value.size)
+        value.iterator().forEach {
+            FfiConverterTypeRepoInfo.write(it, buf)
+        }
+    }
+}
+
+
+
+
+/**
+ * @suppress
+ */
+public object FfiConverterSequenceTypeFileChange: FfiConverterRustBuffer<List<FileChange>> {
+    override fun read(buf: ByteBuffer): List<FileChange> {
+        val len = buf.getInt()
+        return List<FileChange>(len) {
+            FfiConverterTypeFileChange.read(buf)
+        }
+    }
+
+    override fun allocationSize(value: List<FileChange>): ULong {
+        val sizeForLength = 4UL
+        val sizeForItems = value.map { FfiConverterTypeFileChange.allocationSize(it) }.sum()
+        return sizeForLength + sizeForItems
+    }
+
+    override fun write(value: List<FileChange>, buf: ByteBuffer) {
+        buf.putInt(value.size)
+        value.iterator().forEach {
+            FfiConverterTypeFileChange.write(it, buf)
+        }
+    }
+}
+    @Throws(CoreException::class) fun `emergencyCommit`(`dataDir`: kotlin.String, `daemonFullAddr`: kotlin.String, `repo`: kotlin.String, `filePath`: kotlin.String, `newContent`: kotlin.String, `commitMessage`: kotlin.String): kotlin.String {
             return FfiConverterString.lift(
     uniffiRustCallWithError(CoreException) { _status ->
     UniffiLib.INSTANCE.uniffi_mobile_core_fn_func_emergency_commit(
-        FfiConverterString.lower(`dataDir`),FfiConverterString.lower(`daemonFullAddr`),FfiConverterString.lower(`repoPath`),FfiConverterString.lower(`filePath`),FfiConverterString.lower(`newContent`),FfiConverterString.lower(`commitMessage`),_status)
+        FfiConverterString.lower(`dataDir`),FfiConverterString.lower(`daemonFullAddr`),FfiConverterString.lower(`repo`),FfiConverterString.lower(`filePath`),FfiConverterString.lower(`newContent`),FfiConverterString.lower(`commitMessage`),_status)
 }
     )
     }
     
 
-    @Throws(CoreException::class) fun `pair`(`dataDir`: kotlin.String, `daemonFullAddr`: kotlin.String)
+    @Throws(CoreException::class) fun `emergencyCommitFiles`(`dataDir`: kotlin.String, `daemonFullAddr`: kotlin.String, `repo`: kotlin.String, `changes`: List<FileChange>, `commitMessage`: kotlin.String, `options`: CommitOptions): CommitOutcome {
+            return FfiConverterTypeCommitOutcome.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_mobile_core_fn_func_emergency_commit_files(
+        FfiConverterString.lower(`dataDir`),FfiConverterString.lower(`daemonFullAddr`),FfiConverterString.lower(`repo`),FfiConverterSequenceTypeFileChange.lower(`changes`),FfiConverterString.lower(`commitMessage`),FfiConverterTypeCommitOptions.lower(`options`),_status)
+}
+    )
+    }
+    
+
+    @Throws(CoreException::class) fun `listRepos`(`dataDir`: kotlin.String, `daemonFullAddr`: kotlin.String): List<RepoInfo> {
+            return FfiConverterSequenceTypeRepoInfo.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_mobile_core_fn_func_list_repos(
+        FfiConverterString.lower(`dataDir`),FfiConverterString.lower(`daemonFullAddr`),_status)
+}
+    )
+    }
+    
+
+    @Throws(CoreException::class) fun `pair`(`dataDir`: kotlin.String, `daemonFullAddr`: kotlin.String, `code`: kotlin.String)
         = 
     uniffiRustCallWithError(CoreException) { _status ->
     UniffiLib.INSTANCE.uniffi_mobile_core_fn_func_pair(
-        FfiConverterString.lower(`dataDir`),FfiConverterString.lower(`daemonFullAddr`),_status)
+        FfiConverterString.lower(`dataDir`),FfiConverterString.lower(`daemonFullAddr`),FfiConverterString.lower(`code`),_status)
+}
+    
+    
+
+    @Throws(CoreException::class) fun `parsePairingUri`(`uri`: kotlin.String): PairingInfo {
+            return FfiConverterTypePairingInfo.lift(
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_mobile_core_fn_func_parse_pairing_uri(
+        FfiConverterString.lower(`uri`),_status)
+}
+    )
+    }
+    
+
+    @Throws(CoreException::class) fun `setNetworkOptions`(`options`: NetworkOptions)
+        = 
+    uniffiRustCallWithError(CoreException) { _status ->
+    UniffiLib.INSTANCE.uniffi_mobile_core_fn_func_set_network_options(
+        FfiConverterTypeNetworkOptions.lower(`options`),_status)
 }
     
     
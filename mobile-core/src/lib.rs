@@ -1,23 +1,35 @@
 use anyhow::Result;
 use futures::StreamExt;
-use futures::TryFutureExt;
 use libp2p::{
-    gossipsub, mdns, noise, tcp, yamux,
-    swarm::{SwarmEvent, SwarmBuilder, NetworkBehaviour},
-    identity, PeerId, Transport,
+    mdns,
+    multiaddr::Protocol,
+    pnet::PreSharedKey,
+    swarm::{behaviour::toggle::Toggle, SwarmEvent, SwarmBuilder, NetworkBehaviour},
+    identity, PeerId, Swarm,
     kad::{self, store::MemoryStore},
     identify,
     relay,
+    dcutr,
+    autonat,
+    request_response::{self, ProtocolSupport},
     Multiaddr,
 };
+use std::borrow::Cow;
+use std::iter;
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
 use std::time::Duration;
 use std::fs;
 use std::path::Path;
-use tokio::select;
 use thiserror::Error;
+use uuid::Uuid;
 
-mod protocol;
-use protocol::{CommitRequest, NetworkMessage};
+use protocol::{
+    Capability, CommitRequest, Envelope, ErrorKind, Handshake, NetworkMessage, PairingUri, RemoteCommitCodec,
+    RemoteCommitProtocol, KADEMLIA_PROTOCOL, PROTOCOL_VERSION, PUBLIC_BOOTSTRAP_NODES,
+};
+use protocol::{parse_pre_shared_key, pre_shared_key_hex};
+use p2p_node::build_transport;
 
 #[derive(Debug, Error)]
 pub enum CoreError {
@@ -29,15 +41,142 @@ pub enum CoreError {
 
     #[error("The operation timed out.")]
     Timeout,
+
+    #[error("The branch has moved since the changes were made: {message}")]
+    Conflict { message: String },
+
+    #[error("The daemon rejected a file path: {message}")]
+    InvalidPath { message: String },
+
+    #[error("The daemon does not allow this: {message}")]
+    Forbidden { message: String },
+
+    #[error("The daemon is not compatible with this app: {message}")]
+    IncompatibleDaemon { message: String },
+
+    #[error("{message}")]
+    InvalidPairingUri { message: String },
+}
+
+/// Optional settings for where the daemon should commit a changeset.
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    /// Branch to commit to. `None` uses the repository's default branch on the daemon.
+    pub branch: Option<String>,
+    /// Revision to create `branch` from if it doesn't exist yet.
+    pub create_branch_from: Option<String>,
+    /// Commit the changes were based on. The commit fails with
+    /// `CoreError::Conflict` if the branch has moved since.
+    pub expected_parent: Option<String>,
+    /// Ask the daemon to push the branch to its configured remote after committing.
+    pub push: bool,
+}
+
+/// A single operation in a changeset. UniFFI only exports types defined in this crate,
+/// so this mirrors `protocol::FileChange`.
+#[derive(Debug, Clone)]
+pub enum FileChange {
+    Write { path: String, content: String },
+    Delete { path: String },
+    Rename { from: String, to: String },
+}
+
+impl From<FileChange> for protocol::FileChange {
+    fn from(change: FileChange) -> Self {
+        match change {
+            FileChange::Write { path, content } => protocol::FileChange::Write { path, content },
+            FileChange::Delete { path } => protocol::FileChange::Delete { path },
+            FileChange::Rename { from, to } => protocol::FileChange::Rename { from, to },
+        }
+    }
 }
 
+/// Whether the daemon pushed the new commit to its remote.
+#[derive(Debug, Clone)]
+pub struct PushResult {
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+impl From<protocol::PushResult> for PushResult {
+    fn from(result: protocol::PushResult) -> Self {
+        Self { success: result.success, error_message: result.error_message }
+    }
+}
+
+/// A repository from the daemon's registry that this client may commit to.
+#[derive(Debug, Clone)]
+pub struct RepoInfo {
+    pub alias: String,
+    pub default_branch: Option<String>,
+    /// Whether the daemon can push this repository, and lets this client ask it to.
+    pub can_push: bool,
+}
+
+impl From<protocol::RepoInfo> for RepoInfo {
+    fn from(repo: protocol::RepoInfo) -> Self {
+        Self { alias: repo.alias, default_branch: repo.default_branch, can_push: repo.can_push }
+    }
+}
+
+/// What the daemon did with a changeset.
+#[derive(Debug, Clone)]
+pub struct CommitOutcome {
+    pub commit_hash: String,
+    /// Whether the push succeeded. `None` when no push was requested.
+    pub push_result: Option<PushResult>,
+}
+
+/// What a scanned `remote-commit://` pairing URI contains.
+#[derive(Debug, Clone)]
+pub struct PairingInfo {
+    pub peer_id: String,
+    /// Full daemon addresses, ending in `/p2p/<peer id>`. Join them with commas and pass
+    /// them as `daemon_full_addr`, so the client dials all of them, TCP and QUIC alike.
+    pub addresses: Vec<String>,
+    /// One-time code to pass to `pair`.
+    pub code: String,
+    /// Key of the daemon's private network. When set, pass it to `set_network_options`
+    /// with `private_network` before pairing.
+    pub pre_shared_key: Option<String>,
+}
+
+/// How the client joins the DHT, see `set_network_options`.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkOptions {
+    /// Stay off the public IPFS DHT and use the private one. Must match the daemon's
+    /// `network.private` setting.
+    pub private_network: bool,
+    /// Full multiaddrs, ending in `/p2p/<peer id>`, to bootstrap the DHT from. `None` uses
+    /// the public IPFS nodes, or none at all on a private network.
+    pub bootstrap_nodes: Option<Vec<String>>,
+    /// Key of the daemon's private network (pnet), as 64 hex characters. Needs `private_network`.
+    pub pre_shared_key: Option<String>,
+}
+
+/// `NetworkOptions` with the bootstrap nodes parsed.
+#[derive(Clone)]
+struct NetworkSettings {
+    private_network: bool,
+    bootstrap_nodes: Vec<(PeerId, Multiaddr)>,
+    pre_shared_key: Option<PreSharedKey>,
+}
+
+/// Set by `set_network_options`. `None` joins the public DHT.
+static NETWORK_SETTINGS: Mutex<Option<NetworkSettings>> = Mutex::new(None);
+
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "ClientBehaviourEvent")]
 struct ClientBehaviour {
-    gossipsub: gossipsub::Behaviour,
-    mdns: mdns::tokio::Behaviour,
+    request_response: request_response::Behaviour<RemoteCommitCodec>,
+    /// Off when mDNS couldn't start, e.g. on Android without the multicast permission.
+    mdns: Toggle<mdns::tokio::Behaviour>,
     identify: identify::Behaviour,
-    relay: relay::Behaviour,
+    /// Dials daemons behind NAT through the relay they reserved a slot on.
+    relay_client: relay::client::Behaviour,
+    /// Tries to replace a relayed connection with a direct one.
+    dcutr: dcutr::Behaviour,
+    autonat: autonat::Behaviour,
     kademlia: kad::Kademlia<MemoryStore>,
 }
 
@@ -62,112 +201,333 @@ fn get_or_create_identity(data_dir: &str) -> Result<identity::Keypair, CoreError
     }
 }
 
-// Async implementation
-pub async fn emergency_commit_async(
-    data_dir: String,
-    daemon_full_addr: String,
-    repo_path: String,
-    file_path: String,
-    new_content: String,
-    commit_message: String,
-) -> Result<String, CoreError> {
-    let id_keys = get_or_create_identity(&data_dir)?;
+/// Splits a bootstrap node's full multiaddr into the peer and its address.
+fn parse_bootstrap_node(address: &str) -> Result<(PeerId, Multiaddr), CoreError> {
+    let invalid = |reason: String| CoreError::NetworkError {
+        message: format!("Invalid bootstrap node {}: {}", address, reason),
+    };
+    let multiaddr: Multiaddr = address.parse().map_err(|e| invalid(format!("{}", e)))?;
+    match multiaddr.iter().last() {
+        Some(libp2p::multiaddr::Protocol::P2p(hash)) => PeerId::from_multihash(hash)
+            .map(|peer_id| (peer_id, multiaddr))
+            .map_err(|_| invalid("bad peer ID".to_string())),
+        _ => Err(invalid("it must end with /p2p/<peer id>".to_string())),
+    }
+}
+
+fn network_settings(options: NetworkOptions) -> Result<NetworkSettings, CoreError> {
+    let bootstrap_nodes = match options.bootstrap_nodes {
+        Some(nodes) => nodes.iter().map(|address| parse_bootstrap_node(address)).collect::<Result<_, _>>()?,
+        None if options.private_network => Vec::new(),
+        None => PUBLIC_BOOTSTRAP_NODES.iter().map(|address| parse_bootstrap_node(address)).collect::<Result<_, _>>()?,
+    };
+    let pre_shared_key = match options.pre_shared_key {
+        Some(_) if !options.private_network => {
+            return Err(CoreError::NetworkError {
+                message: "A pre-shared key only works on a private network".to_string(),
+            })
+        }
+        Some(hex) => Some(parse_pre_shared_key(&hex).map_err(|_| CoreError::NetworkError {
+            message: "The pre-shared key must be 64 hex characters".to_string(),
+        })?),
+        None => None,
+    };
+    Ok(NetworkSettings { private_network: options.private_network, bootstrap_nodes, pre_shared_key })
+}
+
+/// Sets how every later call joins the DHT. Without it, the client uses the public
+/// IPFS DHT. Peers are always found through mDNS and the daemon address as well.
+pub fn set_network_options(options: NetworkOptions) -> Result<(), CoreError> {
+    let settings = network_settings(options)?;
+    *NETWORK_SETTINGS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(settings);
+    Ok(())
+}
+
+/// Builds the swarm every request to the daemon goes through.
+fn build_swarm(id_keys: &identity::Keypair) -> Result<Swarm<ClientBehaviour>, CoreError> {
     let local_peer_id = PeerId::from(id_keys.public());
-    println!("Client Peer ID: {}", local_peer_id);
-
-    let transport = tcp::tokio::Transport::default()
-        .upgrade(libp2p::core::upgrade::Version::V1)
-        .authenticate(noise::Config::new(&id_keys).unwrap())
-        .multiplex(yamux::Config::default())
-        .boxed();
-
-    let topic = gossipsub::IdentTopic::new("emergency-git-commits");
-    let commit_request = CommitRequest { repo_path, file_path, new_content, commit_message };
-    let mut swarm = {
-        let store = MemoryStore::new(local_peer_id);
-        let mut kademlia = kad::Kademlia::new(local_peer_id, store);
-        let bootstrap_nodes = [
-            "/ip4/104.131.131.82/tcp/4001/p2p/QmaCpDMGvV2BGHeYERUEnRQAwe3N8SzbUtfsmvsqQLuvuJ",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmQCU2EcMqAqQPR2i9bChDtGNJchTbq5TbXJJ16u19uLTa",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmcZf59bWwK5XFi76CZX8cbJ4BhTzzA3gU1ZjYZcYW3dwt"
-        ];
-        for addr in bootstrap_nodes {
-            let multiaddr: Multiaddr = addr.parse().expect("Failed to parse bootstrap address");
-            if let Some(libp2p::multiaddr::Protocol::P2p(hash)) = multiaddr.iter().last() {
-                let peer_id = PeerId::from_multihash(hash).expect("Valid PeerId multihash");
-                kademlia.add_address(&peer_id, multiaddr);
-            } else {
-                eprintln!("Could not extract PeerId from bootstrap address: {}", addr);
-            }
+    let settings = match NETWORK_SETTINGS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone() {
+        Some(settings) => settings,
+        None => network_settings(NetworkOptions::default())?,
+    };
+    // Relayed addresses (`.../p2p-circuit/p2p/<daemon>`) go through the relay transport
+    let (relay_transport, relay_client) = relay::client::new(local_peer_id);
+    let transport = build_transport(id_keys, Some(relay_transport), settings.pre_shared_key, true)
+        .map_err(|e| CoreError::NetworkError { message: format!("Failed to set up encryption: {}", e) })?;
+
+    let store = MemoryStore::new(local_peer_id);
+    let mut kademlia_config = kad::KademliaConfig::default();
+    if settings.private_network {
+        kademlia_config.set_protocol_names(vec![Cow::Borrowed(KADEMLIA_PROTOCOL)]);
+    }
+    let mut kademlia = kad::Kademlia::with_config(local_peer_id, store, kademlia_config);
+    for (peer_id, multiaddr) in settings.bootstrap_nodes {
+        kademlia.add_address(&peer_id, multiaddr);
+    }
+    // Nothing to bootstrap from on a private network without bootstrap nodes, which is fine:
+    // the daemon is dialed directly.
+    if let Err(e) = kademlia.bootstrap() {
+        println!("Not bootstrapping the DHT: {}", e);
+    }
+    // Only finds daemons on the local network, so explicit addresses work without it
+    let mdns = match mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id) {
+        Ok(mdns) => Some(mdns),
+        Err(e) => {
+            println!("Not discovering daemons on the local network, mDNS failed to start: {}", e);
+            None
         }
-        kademlia.bootstrap().unwrap();
-        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id).unwrap();
-        let gossipsub = gossipsub::Behaviour::new(
-            gossipsub::MessageAuthenticity::Signed(id_keys.clone()),
-            gossipsub::Config::default(),
-        ).unwrap();
-        let behaviour = ClientBehaviour {
-            gossipsub,
-            mdns,
-            identify: identify::Behaviour::new(identify::Config::new(
-                "/emergency-git/1.0".into(),
-                id_keys.public(),
-            )),
-            relay: relay::Behaviour::new(local_peer_id, Default::default()),
-            kademlia,
-        };
-        SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id).build()
     };
-    swarm.behaviour_mut().gossipsub.subscribe(&topic).unwrap();
+    // Commits that push can take a while, so wait longer than the default for the reply
+    let mut request_response_config = request_response::Config::default();
+    request_response_config.set_request_timeout(Duration::from_secs(60));
+    let request_response = request_response::Behaviour::new(
+        RemoteCommitCodec,
+        iter::once((RemoteCommitProtocol, ProtocolSupport::Outbound)),
+        request_response_config,
+    );
+    let behaviour = ClientBehaviour {
+        request_response,
+        mdns: mdns.into(),
+        identify: identify::Behaviour::new(identify::Config::new(
+            "/emergency-git/1.0".into(),
+            id_keys.public(),
+        )),
+        relay_client,
+        dcutr: dcutr::Behaviour::new(local_peer_id),
+        autonat: autonat::Behaviour::new(local_peer_id, Default::default()),
+        kademlia,
+    };
+    let mut swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id).build();
+    // DCUtR punches holes from the port we listen on, and the daemon dials back to it
+    let any_address = Multiaddr::empty().with(Protocol::Ip4(Ipv4Addr::UNSPECIFIED));
+    let mut listen_addresses = vec![any_address.clone().with(Protocol::Tcp(0))];
+    if settings.pre_shared_key.is_none() {
+        listen_addresses.push(any_address.with(Protocol::Udp(0)).with(Protocol::QuicV1));
+    }
+    for address in listen_addresses {
+        swarm.listen_on(address.clone())
+            .map_err(|e| CoreError::NetworkError { message: format!("Failed to listen on {}: {}", address, e) })?;
+    }
+    Ok(swarm)
+}
+
+/// What this client tells the daemon it can use in the handshake.
+const CAPABILITIES: &[Capability] = &[
+    Capability::MultiFile,
+    Capability::Branches,
+    Capability::Push,
+    Capability::ListRepos,
+];
 
-    // --- NEW: Direct Dial Logic ---
-    let daemon_addr: Multiaddr = daemon_full_addr.parse()
+/// Splits a full daemon address into the daemon's peer ID and the address.
+fn parse_daemon_address(address: &str) -> Result<(PeerId, Multiaddr), CoreError> {
+    // The address has to name the daemon, since requests are sent to that peer only
+    let daemon_addr: Multiaddr = address.parse()
         .map_err(|e| CoreError::NetworkError { message: format!("Invalid daemon address: {}", e) })?;
-    if let Err(e) = swarm.dial(daemon_addr) {
-        return Err(CoreError::NetworkError { message: format!("Failed to dial daemon: {}", e) });
+    match daemon_addr.iter().last() {
+        Some(libp2p::multiaddr::Protocol::P2p(hash)) => PeerId::from_multihash(hash)
+            .map(|peer_id| (peer_id, daemon_addr))
+            .map_err(|_| CoreError::NetworkError { message: "Invalid daemon peer ID".to_string() }),
+        _ => Err(CoreError::NetworkError {
+            message: format!("Daemon address must end with /p2p/<peer id>: {}", address),
+        }),
+    }
+}
+
+/// A swarm dialed to one daemon that has answered the handshake.
+struct DaemonConnection {
+    swarm: Swarm<ClientBehaviour>,
+    daemon_peer_id: PeerId,
+    /// What the daemon said it supports.
+    capabilities: Vec<Capability>,
+}
+
+impl DaemonConnection {
+    /// Dials the daemon at `daemon_full_addr` and checks that it speaks our protocol version.
+    /// `daemon_full_addr` may hold several comma separated addresses of the same daemon,
+    /// which are all dialed at once, and the first connection to succeed is used.
+    async fn open(data_dir: &str, daemon_full_addr: &str) -> Result<Self, CoreError> {
+        let id_keys = get_or_create_identity(data_dir)?;
+        println!("Client Peer ID: {}", PeerId::from(id_keys.public()));
+        let mut swarm = build_swarm(&id_keys)?;
+
+        let mut daemon_peer_id = None;
+        for address in daemon_full_addr.split(',').map(str::trim).filter(|address| !address.is_empty()) {
+            let (peer_id, daemon_addr) = parse_daemon_address(address)?;
+            if daemon_peer_id.is_some_and(|daemon_peer_id| daemon_peer_id != peer_id) {
+                return Err(CoreError::NetworkError {
+                    message: format!("Daemon addresses name different peers: {}", daemon_full_addr),
+                });
+            }
+            daemon_peer_id = Some(peer_id);
+            swarm.behaviour_mut().request_response.add_address(&peer_id, daemon_addr);
+        }
+        let daemon_peer_id = daemon_peer_id.ok_or_else(|| CoreError::NetworkError {
+            message: "No daemon address given".to_string(),
+        })?;
+        let mut connection = Self { swarm, daemon_peer_id, capabilities: Vec::new() };
+
+        println!("Dialing daemon... waiting for the handshake.");
+        let hello = Handshake { version: PROTOCOL_VERSION, capabilities: CAPABILITIES.to_vec() };
+        let handshake = match connection.request(NetworkMessage::Hello(hello)).await? {
+            NetworkMessage::Hello(handshake) => handshake,
+            _ => {
+                return Err(CoreError::IncompatibleDaemon {
+                    message: "The daemon did not answer the handshake".to_string(),
+                })
+            }
+        };
+        if handshake.version != PROTOCOL_VERSION {
+            return Err(CoreError::IncompatibleDaemon {
+                message: format!(
+                    "The daemon speaks protocol version {}, this app speaks {}",
+                    handshake.version, PROTOCOL_VERSION
+                ),
+            });
+        }
+        connection.capabilities = handshake.capabilities;
+        Ok(connection)
+    }
+
+    /// Fails unless the daemon said it supports `capability`.
+    fn require(&self, capability: Capability) -> Result<(), CoreError> {
+        if self.capabilities.contains(&capability) {
+            Ok(())
+        } else {
+            Err(CoreError::IncompatibleDaemon {
+                message: format!("The daemon does not support {:?}", capability),
+            })
+        }
     }
-    println!("Dialing daemon... waiting for connection.");
-    let mut published_request = false;
-    loop {
-        select! {
-            event = swarm.select_next_some() => match event {
+
+    /// Sends `request` under a fresh request id and waits for the reply carrying that id.
+    async fn request(&mut self, request: NetworkMessage) -> Result<NetworkMessage, CoreError> {
+        let envelope = Envelope::new(Uuid::new_v4().to_string(), request);
+        let expected_id = envelope.request_id.clone();
+        let request_id = self.swarm.behaviour_mut().request_response.send_request(&self.daemon_peer_id, envelope);
+        loop {
+            match self.swarm.select_next_some().await {
                 SwarmEvent::ConnectionEstablished { peer_id, .. } => {
                     println!("✅ Successfully connected to daemon: {}", peer_id);
                 }
-                SwarmEvent::Behaviour(ClientBehaviourEvent::Gossipsub(gossipsub::Event::Subscribed { peer_id, .. })) => {
-                    if !published_request {
-                        let request_message = NetworkMessage::Request(commit_request.clone());
-                        let request_json = serde_json::to_string(&request_message)
-                            .map_err(|e| CoreError::JsonError { message: e.to_string() })?;
-                        if swarm.behaviour_mut().gossipsub.publish(topic.clone(), request_json.as_bytes()).is_ok() {
-                            published_request = true;
-                        }
-                    }
-                },
-                SwarmEvent::Behaviour(ClientBehaviourEvent::Gossipsub(gossipsub::Event::Message { message, .. })) => {
-                    if let Ok(NetworkMessage::Response(response)) = serde_json::from_slice(&message.data) {
-                        return if response.success {
-                            Ok(response.commit_hash.unwrap_or_default())
-                        } else {
-                            Err(CoreError::NetworkError { message: response.error_message.unwrap_or_default() })
-                        }
+                SwarmEvent::Behaviour(ClientBehaviourEvent::RequestResponse(request_response::Event::Message {
+                    message: request_response::Message::Response { request_id: id, response },
+                    ..
+                })) if id == request_id => {
+                    if response.request_id != expected_id {
+                        return Err(CoreError::NetworkError {
+                            message: format!("The daemon answered request {} instead of {}", response.request_id, expected_id),
+                        });
                     }
+                    return Ok(response.message);
+                }
+                SwarmEvent::Behaviour(ClientBehaviourEvent::RequestResponse(request_response::Event::OutboundFailure {
+                    request_id: id,
+                    error,
+                    ..
+                })) if id == request_id => {
+                    return Err(match error {
+                        request_response::OutboundFailure::Timeout => CoreError::Timeout,
+                        request_response::OutboundFailure::UnsupportedProtocols => CoreError::IncompatibleDaemon {
+                            message: "The daemon does not speak the remote-commit protocol".to_string(),
+                        },
+                        error => CoreError::NetworkError { message: format!("Request to daemon failed: {}", error) },
+                    });
                 }
                 _ => {}
-            },
-            _ = tokio::time::sleep(Duration::from_secs(20)) => {
-                return Err(CoreError::Timeout);
             }
         }
     }
 }
 
+/// Connects to the daemon, checks it supports every capability in `required`, then
+/// sends `request` and turns the reply into the result with `extract`.
+async fn send_to_daemon<T>(
+    data_dir: &str,
+    daemon_full_addr: &str,
+    request: NetworkMessage,
+    required: &[Capability],
+    extract: impl Fn(NetworkMessage) -> Option<T>,
+) -> Result<T, CoreError> {
+    let mut connection = DaemonConnection::open(data_dir, daemon_full_addr).await?;
+    for capability in required {
+        connection.require(*capability)?;
+    }
+    let reply = connection.request(request).await?;
+    extract(reply).ok_or_else(|| CoreError::NetworkError {
+        message: "The daemon sent an unexpected reply".to_string(),
+    })
+}
+
+// Async implementation
+pub async fn emergency_commit_async(
+    data_dir: String,
+    daemon_full_addr: String,
+    repo: String,
+    file_path: String,
+    new_content: String,
+    commit_message: String,
+) -> Result<String, CoreError> {
+    let changes = vec![FileChange::Write { path: file_path, content: new_content }];
+    emergency_commit_files_async(data_dir, daemon_full_addr, repo, changes, commit_message, CommitOptions::default())
+        .await
+        .map(|outcome| outcome.commit_hash)
+}
+
+/// Sends several file changes that the daemon commits together in a single commit.
+/// `repo` is the alias the daemon's repository registry knows the repository by.
+pub async fn emergency_commit_files_async(
+    data_dir: String,
+    daemon_full_addr: String,
+    repo: String,
+    changes: Vec<FileChange>,
+    commit_message: String,
+    options: CommitOptions,
+) -> Result<CommitOutcome, CoreError> {
+    let commit_request = CommitRequest {
+        repo,
+        changes: changes.into_iter().map(protocol::FileChange::from).collect(),
+        commit_message,
+        branch: options.branch,
+        create_branch_from: options.create_branch_from,
+        expected_parent: options.expected_parent,
+        push: options.push,
+    };
+    // Older daemons only take a single file written to HEAD
+    let mut required = Vec::new();
+    let single_write = matches!(commit_request.changes.as_slice(), [protocol::FileChange::Write { .. }]);
+    if !single_write {
+        required.push(Capability::MultiFile);
+    }
+    if commit_request.branch.is_some() || commit_request.create_branch_from.is_some() {
+        required.push(Capability::Branches);
+    }
+    if commit_request.push {
+        required.push(Capability::Push);
+    }
+    let response = send_to_daemon(&data_dir, &daemon_full_addr, NetworkMessage::Request(commit_request), &required, |message| match message {
+        NetworkMessage::Response(response) => Some(response),
+        _ => None,
+    }).await?;
+
+    let message = response.error_message.unwrap_or_default();
+    match (response.success, response.error_kind) {
+        (true, _) => Ok(CommitOutcome {
+            commit_hash: response.commit_hash.unwrap_or_default(),
+            push_result: response.push_result.map(PushResult::from),
+        }),
+        (false, Some(ErrorKind::Conflict)) => Err(CoreError::Conflict { message }),
+        (false, Some(ErrorKind::InvalidPath)) => Err(CoreError::InvalidPath { message }),
+        (false, Some(ErrorKind::Forbidden)) => Err(CoreError::Forbidden { message }),
+        (false, _) => Err(CoreError::NetworkError { message }),
+    }
+}
+
 // Synchronous wrapper for UniFFI
 pub fn emergency_commit(
     data_dir: String,
     daemon_full_addr: String,
-    repo_path: String,
+    repo: String,
     file_path: String,
     new_content: String,
     commit_message: String,
@@ -175,98 +535,67 @@ pub fn emergency_commit(
     // Create a new Tokio runtime or use the existing one
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| CoreError::NetworkError { message: format!("Failed to create runtime: {}", e) })?;
-    rt.block_on(emergency_commit_async(data_dir, daemon_full_addr, repo_path, file_path, new_content, commit_message))
+    rt.block_on(emergency_commit_async(data_dir, daemon_full_addr, repo, file_path, new_content, commit_message))
 }
 
-pub async fn pair_async(data_dir: String, daemon_full_addr: String) -> Result<(), CoreError> {
-    let id_keys = get_or_create_identity(&data_dir)?;
-    let local_peer_id = PeerId::from(id_keys.public());
-    println!("Client Peer ID: {}", local_peer_id);
-    let transport = tcp::tokio::Transport::default()
-        .upgrade(libp2p::core::upgrade::Version::V1)
-        .authenticate(noise::Config::new(&id_keys).unwrap())
-        .multiplex(yamux::Config::default())
-        .boxed();
-    let topic = gossipsub::IdentTopic::new("emergency-git-commits");
-    let mut swarm = {
-        let store = MemoryStore::new(local_peer_id);
-        let mut kademlia = kad::Kademlia::new(local_peer_id, store);
-        let bootstrap_nodes = [
-            "/ip4/104.131.131.82/tcp/4001/p2p/QmaCpDMGvV2BGHeYERUEnRQAwe3N8SzbUtfsmvsqQLuvuJ",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmQCU2EcMqAqQPR2i9bChDtGNJchTbq5TbXJJ16u19uLTa",
-            "/dnsaddr/bootstrap.libp2p.io/p2p/QmcZf59bWwK5XFi76CZX8cbJ4BhTzzA3gU1ZjYZcYW3dwt"
-        ];
-        for addr in bootstrap_nodes {
-            let multiaddr: Multiaddr = addr.parse().expect("Failed to parse bootstrap address");
-            if let Some(libp2p::multiaddr::Protocol::P2p(hash)) = multiaddr.iter().last() {
-                let peer_id = PeerId::from_multihash(hash).expect("Valid PeerId multihash");
-                kademlia.add_address(&peer_id, multiaddr);
-            } else {
-                eprintln!("Could not extract PeerId from bootstrap address: {}", addr);
-            }
-        }
-        kademlia.bootstrap().unwrap();
-        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id).unwrap();
-        let gossipsub = gossipsub::Behaviour::new(
-            gossipsub::MessageAuthenticity::Signed(id_keys.clone()),
-            gossipsub::Config::default(),
-        ).unwrap();
-        let behaviour = ClientBehaviour {
-            gossipsub,
-            mdns,
-            identify: identify::Behaviour::new(identify::Config::new(
-                "/emergency-git/1.0".into(),
-                id_keys.public(),
-            )),
-            relay: relay::Behaviour::new(local_peer_id, Default::default()),
-            kademlia,
-        };
-        SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id).build()
-    };
-    swarm.behaviour_mut().gossipsub.subscribe(&topic).unwrap();
-    // --- NEW: Direct Dial Logic ---
-    let daemon_addr: Multiaddr = daemon_full_addr.parse()
-        .map_err(|e| CoreError::NetworkError { message: format!("Invalid daemon address: {}", e) })?;
-    if let Err(e) = swarm.dial(daemon_addr) {
-        return Err(CoreError::NetworkError { message: format!("Failed to dial daemon: {}", e) });
-    }
-    println!("Dialing daemon... waiting for connection.");
-    let mut published_request = false;
-    loop {
-        select! {
-            event = swarm.select_next_some() => match event {
-                SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                    println!("✅ Successfully connected to daemon: {}", peer_id);
-                }
-                SwarmEvent::Behaviour(ClientBehaviourEvent::Gossipsub(gossipsub::Event::Subscribed { peer_id, .. })) => {
-                    if !published_request {
-                        let request_message = NetworkMessage::PairRequest;
-                        let request_json = serde_json::to_string(&request_message).unwrap();
-                        if swarm.behaviour_mut().gossipsub.publish(topic.clone(), request_json.as_bytes()).is_ok() {
-                            published_request = true;
-                            println!("Pairing request sent. Waiting for approval on daemon...");
-                        }
-                    }
-                },
-                SwarmEvent::Behaviour(ClientBehaviourEvent::Gossipsub(gossipsub::Event::Message { message, .. })) => {
-                    if let Ok(NetworkMessage::PairSuccess) = serde_json::from_slice(&message.data) {
-                        return Ok(());
-                    }
-                }
-                _ => {}
-            },
-            _ = tokio::time::sleep(Duration::from_secs(20)) => {
-                return Err(CoreError::Timeout);
-            }
-        }
-    }
+// Synchronous wrapper for UniFFI
+pub fn emergency_commit_files(
+    data_dir: String,
+    daemon_full_addr: String,
+    repo: String,
+    changes: Vec<FileChange>,
+    commit_message: String,
+    options: CommitOptions,
+) -> Result<CommitOutcome, CoreError> {
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| CoreError::NetworkError { message: format!("Failed to create runtime: {}", e) })?;
+    rt.block_on(emergency_commit_files_async(data_dir, daemon_full_addr, repo, changes, commit_message, options))
+}
+
+/// Pairs with the daemon using a one-time `code` from `daemon pair-code`.
+/// A wrong, expired or already used code fails with `CoreError::Forbidden`.
+pub async fn pair_async(data_dir: String, daemon_full_addr: String, code: String) -> Result<(), CoreError> {
+    println!("Sending pairing request...");
+    send_to_daemon(&data_dir, &daemon_full_addr, NetworkMessage::PairRequest { code }, &[], |message| match message {
+        NetworkMessage::PairSuccess => Some(Ok(())),
+        NetworkMessage::PairDenied { reason } => Some(Err(CoreError::Forbidden { message: reason })),
+        _ => None,
+    }).await?
 }
 
-pub fn pair(data_dir: String, daemon_full_addr: String) -> Result<(), CoreError> {
+pub fn pair(data_dir: String, daemon_full_addr: String, code: String) -> Result<(), CoreError> {
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| CoreError::NetworkError { message: format!("Failed to create runtime: {}", e) })?;
+    rt.block_on(pair_async(data_dir, daemon_full_addr, code))
+}
+
+/// Reads a `remote-commit://` URI, usually scanned from the QR code `daemon pair-code` prints.
+pub fn parse_pairing_uri(uri: String) -> Result<PairingInfo, CoreError> {
+    let pairing_uri: PairingUri = uri.parse()
+        .map_err(|e: protocol::InvalidPairingUri| CoreError::InvalidPairingUri { message: e.to_string() })?;
+    Ok(PairingInfo {
+        peer_id: pairing_uri.peer_id.to_string(),
+        addresses: pairing_uri.full_addresses().iter().map(|address| address.to_string()).collect(),
+        code: pairing_uri.code,
+        pre_shared_key: pairing_uri.pre_shared_key.as_ref().map(pre_shared_key_hex),
+    })
+}
+
+/// Asks the daemon which repositories this client may commit to.
+/// An unpaired client, or one whose trust has expired, gets `CoreError::Forbidden`.
+pub async fn list_repos_async(data_dir: String, daemon_full_addr: String) -> Result<Vec<RepoInfo>, CoreError> {
+    send_to_daemon(&data_dir, &daemon_full_addr, NetworkMessage::ListRepos, &[Capability::ListRepos], |message| match message {
+        NetworkMessage::RepoList(repos) => Some(Ok(repos.into_iter().map(RepoInfo::from).collect())),
+        NetworkMessage::Denied { reason } => Some(Err(CoreError::Forbidden { message: reason })),
+        _ => None,
+    }).await?
+}
+
+// Synchronous wrapper for UniFFI
+pub fn list_repos(data_dir: String, daemon_full_addr: String) -> Result<Vec<RepoInfo>, CoreError> {
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| CoreError::NetworkError { message: format!("Failed to create runtime: {}", e) })?;
-    rt.block_on(pair_async(data_dir, daemon_full_addr))
+    rt.block_on(list_repos_async(data_dir, daemon_full_addr))
 }
 
 uniffi::include_scaffolding!("mobile_core");
\ No newline at end of file
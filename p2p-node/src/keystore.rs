@@ -0,0 +1,155 @@
+// Persists a node's libp2p keypair, so its PeerId survives restarts
+use anyhow::{Context, Result};
+use libp2p::identity;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Loads the keypair in `key_file` under `data_dir`, or creates (and saves) a new one if there is none.
+pub fn get_or_create_identity(data_dir: &Path, key_file: &str) -> Result<identity::Keypair> {
+    create_private_dir(data_dir)?;
+    let keypair_path = data_dir.join(key_file);
+    if keypair_path.exists() {
+        println!("Loading existing identity from {:?}...", keypair_path);
+        return load_identity(&keypair_path);
+    }
+    println!("No identity found. Generating a new one at {:?}...", keypair_path);
+    let keypair = identity::Keypair::generate_ed25519();
+    let encoded_key = keypair.to_protobuf_encoding()
+        .with_context(|| "Failed to encode keypair")?;
+    if write_private_file(&keypair_path, &encoded_key)? {
+        Ok(keypair)
+    } else {
+        // Another process, e.g. `daemon pair-code` next to the daemon, saved one first
+        println!("Another process created {:?} first, loading it instead...", keypair_path);
+        load_identity(&keypair_path)
+    }
+}
+
+fn load_identity(keypair_path: &Path) -> Result<identity::Keypair> {
+    check_private_file(keypair_path)?;
+    let key_bytes = fs::read(keypair_path)
+        .with_context(|| format!("Failed to read key file {:?}", keypair_path))?;
+    identity::Keypair::from_protobuf_encoding(&key_bytes)
+        .with_context(|| format!("Failed to decode key file {:?}", keypair_path))
+}
+
+/// Creates `dir` if needed, readable only by the current user.
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        return Ok(());
+    }
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+        .with_context(|| format!("Failed to create data directory {:?}", dir))
+}
+
+/// Creates a file that only the current user can read, unless `path` already exists.
+/// Returns whether this call created it. The contents go to a temporary file that is
+/// then linked into place, so a concurrent reader never sees a half-written file.
+fn write_private_file(path: &Path, contents: &[u8]) -> Result<bool> {
+    // Unique across processes and threads, so concurrent writers never share it
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let write_number = WRITES.fetch_add(1, Ordering::Relaxed);
+    let temp_path = PathBuf::from(format!("{}.{}-{}.tmp", path.display(), std::process::id(), write_number));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&temp_path)
+        .with_context(|| format!("Failed to create {:?}", temp_path))?;
+    let linked = file.write_all(contents)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("Failed to write {:?}", temp_path))
+        .and_then(|()| match fs::hard_link(&temp_path, path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("Failed to create {:?}", path)),
+        });
+    let _ = fs::remove_file(&temp_path);
+    linked
+}
+
+/// Refuses to use a private key that other users can read, like ssh does.
+fn check_private_file(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)
+            .with_context(|| format!("Failed to read permissions of {:?}", path))?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            return Err(anyhow::anyhow!(
+                "Permissions {:o} for {:?} are too open. Run `chmod 600 {}` and start again.",
+                mode & 0o777,
+                path,
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_FILE: &str = "identity.key";
+
+    #[test]
+    fn identity_survives_a_reload() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let created = get_or_create_identity(data_dir.path(), KEY_FILE).unwrap();
+        let loaded = get_or_create_identity(data_dir.path(), KEY_FILE).unwrap();
+        assert_eq!(created.public().to_peer_id(), loaded.public().to_peer_id());
+    }
+
+    #[test]
+    fn concurrent_first_starts_agree_on_one_identity() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let data_dir = data_dir.path().join("fresh");
+        let peer_ids: Vec<_> = std::thread::scope(|scope| {
+            let starts: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| get_or_create_identity(&data_dir, KEY_FILE).unwrap().public().to_peer_id()))
+                .collect();
+            starts.into_iter().map(|start| start.join().unwrap()).collect()
+        });
+        assert!(peer_ids.iter().all(|peer_id| *peer_id == peer_ids[0]));
+        assert_eq!(fs::read_dir(&data_dir).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let data_dir = tempfile::tempdir().unwrap();
+        let data_dir = data_dir.path().join("data");
+        get_or_create_identity(&data_dir, KEY_FILE).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&data_dir.join(KEY_FILE)), 0o600);
+        assert_eq!(mode(&data_dir), 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_file_others_can_read_is_refused() {
+        use std::os::unix::fs::PermissionsExt;
+        let data_dir = tempfile::tempdir().unwrap();
+        get_or_create_identity(data_dir.path(), KEY_FILE).unwrap();
+        let key_path = data_dir.path().join(KEY_FILE);
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o644)).unwrap();
+        let error = get_or_create_identity(data_dir.path(), KEY_FILE).unwrap_err();
+        assert!(error.to_string().contains("too open"), "{}", error);
+    }
+}
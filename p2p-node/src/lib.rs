@@ -0,0 +1,5 @@
+// What the daemon, the relay and clients share to be a node on the network: the transport
+// they connect over and the keypair that gives them a stable PeerId.
+pub mod keystore;
+mod transport;
+pub use transport::build_transport;
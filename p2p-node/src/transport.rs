@@ -0,0 +1,47 @@
+use libp2p::{
+    core::{muxing::StreamMuxerBox, transport::{Boxed, OptionalTransport}, upgrade::Version},
+    identity, noise,
+    pnet::{PnetConfig, PreSharedKey},
+    relay, tcp, yamux, PeerId, Transport,
+};
+
+// TCP, and through `relay_transport` circuits via a relay, both secured with noise and
+// multiplexed with yamux, plus QUIC if `use_quic` is set. With a pre-shared key every TCP
+// connection starts with the pnet handshake, and QUIC stays off since it can't carry one.
+pub fn build_transport(
+    id_keys: &identity::Keypair,
+    relay_transport: Option<relay::client::Transport>,
+    pre_shared_key: Option<PreSharedKey>,
+    use_quic: bool,
+) -> Result<Boxed<(PeerId, StreamMuxerBox)>, noise::Error> {
+    // Port reuse makes outgoing connections leave from the port we listen on, which DCUtR
+    // needs to punch holes. Nodes without a relay transport don't hole punch.
+    let tcp_config = tcp::Config::default().port_reuse(relay_transport.is_some());
+    let relay_transport = match relay_transport {
+        Some(transport) => OptionalTransport::some(transport),
+        None => OptionalTransport::none(),
+    };
+    // Circuits through a relay are secured and multiplexed just like TCP connections
+    let tcp = relay_transport.or_transport(tcp::tokio::Transport::new(tcp_config));
+    let noise = noise::Config::new(id_keys)?;
+    let tcp = match pre_shared_key {
+        Some(key) => tcp
+            .and_then(move |socket, _| PnetConfig::new(key).handshake(socket))
+            .upgrade(Version::V1)
+            .authenticate(noise)
+            .multiplex(yamux::Config::default())
+            .boxed(),
+        None => tcp
+            .upgrade(Version::V1)
+            .authenticate(noise)
+            .multiplex(yamux::Config::default())
+            .boxed(),
+    };
+    if !use_quic || pre_shared_key.is_some() {
+        return Ok(tcp);
+    }
+    // QUIC brings its own encryption and multiplexing, and sets up faster than TCP
+    let quic = libp2p_quic::tokio::Transport::new(libp2p_quic::Config::new(id_keys))
+        .map(|(peer_id, connection), _| (peer_id, StreamMuxerBox::new(connection)));
+    Ok(tcp.or_transport(quic).map(|either, _| either.into_inner()).boxed())
+}
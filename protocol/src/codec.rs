@@ -0,0 +1,71 @@
+// Carries envelopes over libp2p request-response streams
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use libp2p::core::upgrade::{read_length_prefixed, write_length_prefixed};
+use libp2p::request_response::{self, ProtocolName};
+use std::io;
+
+use crate::Envelope;
+
+// Commit requests carry whole files, so allow generously sized messages
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+// The point-to-point protocol clients use to talk to the daemon
+#[derive(Debug, Clone)]
+pub struct RemoteCommitProtocol;
+
+impl ProtocolName for RemoteCommitProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        b"/remote-commit/1.0"
+    }
+}
+
+// Sends an `Envelope` each way as length-prefixed JSON
+#[derive(Debug, Clone, Default)]
+pub struct RemoteCommitCodec;
+
+#[async_trait]
+impl request_response::Codec for RemoteCommitCodec {
+    type Protocol = RemoteCommitProtocol;
+    type Request = Envelope;
+    type Response = Envelope;
+
+    async fn read_request<T>(&mut self, _: &RemoteCommitProtocol, io: &mut T) -> io::Result<Envelope>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io).await
+    }
+
+    async fn read_response<T>(&mut self, _: &RemoteCommitProtocol, io: &mut T) -> io::Result<Envelope>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io).await
+    }
+
+    async fn write_request<T>(&mut self, _: &RemoteCommitProtocol, io: &mut T, request: Envelope) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &request).await
+    }
+
+    async fn write_response<T>(&mut self, _: &RemoteCommitProtocol, io: &mut T, response: Envelope) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &response).await
+    }
+}
+
+async fn read_message<T: AsyncRead + Unpin + Send>(io: &mut T) -> io::Result<Envelope> {
+    let bytes = read_length_prefixed(io, MAX_MESSAGE_SIZE).await?;
+    crate::decode(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+async fn write_message<T: AsyncWrite + Unpin + Send>(io: &mut T, message: &Envelope) -> io::Result<()> {
+    let bytes = crate::encode(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write_length_prefixed(io, bytes).await?;
+    io.close().await
+}
@@ -0,0 +1,162 @@
+// The messages clients and the daemon exchange, shared by every crate that speaks the protocol
+use serde::{Deserialize, Serialize};
+
+mod codec;
+pub use codec::{RemoteCommitCodec, RemoteCommitProtocol};
+mod pairing_uri;
+pub use pairing_uri::{InvalidPairingUri, PairingUri, PAIRING_URI_SCHEME};
+mod psk;
+pub use psk::{parse_pre_shared_key, pre_shared_key_hex};
+
+// Version of the wire format. Bump it whenever a change would break the other side.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// Kademlia protocol name in private mode, so our DHT never mixes with the public IPFS one
+pub const KADEMLIA_PROTOCOL: &[u8] = b"/remote-commit/kad/1.0.0";
+
+// The public IPFS bootstrap nodes, used outside private mode unless others are configured
+pub const PUBLIC_BOOTSTRAP_NODES: &[&str] = &[
+    "/ip4/104.131.131.82/tcp/4001/p2p/QmaCpDMGvV2BGHeYERUEnRQAwe3N8SzbUtfsmvsqQLuvuJ",
+    "/dnsaddr/bootstrap.libp2p.io/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN",
+    "/dnsaddr/bootstrap.libp2p.io/p2p/QmQCU2EcMqAqQPR2i9bChDtGNJchTbq5TbXJJ16u19uLTa",
+    "/dnsaddr/bootstrap.libp2p.io/p2p/QmcZf59bWwK5XFi76CZX8cbJ4BhTzzA3gU1ZjYZcYW3dwt",
+];
+
+// Every message travels in an envelope. The client picks `request_id` and the
+// daemon echoes it in its reply, so a reply can always be matched to its request.
+// Versions are compared once per connection, in the `Hello` handshake.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Envelope {
+    pub request_id: String,
+    pub message: NetworkMessage,
+}
+
+impl Envelope {
+    pub fn new(request_id: String, message: NetworkMessage) -> Self {
+        Self { request_id, message }
+    }
+}
+
+// Serializes an envelope the way it is sent on the wire
+pub fn encode(envelope: &Envelope) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(envelope)
+}
+
+// Parses an envelope received from the wire
+pub fn decode(bytes: &[u8]) -> serde_json::Result<Envelope> {
+    serde_json::from_slice(bytes)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum NetworkMessage {
+    // Client -> Daemon: the first request on every connection, with the client's version.
+    // Daemon -> Client: the daemon's version and what it supports. Anyone may send it, paired or not.
+    Hello(Handshake),
+
+    // Client -> Daemon: "I'd like to pair with you." `code` is a one-time code the daemon issued.
+    PairRequest { code: String },
+    // Daemon -> Client: "Okay, I've saved you as a trusted peer."
+    PairSuccess,
+    // Daemon -> Client: the code was wrong, expired or already used.
+    PairDenied { reason: String },
+    
+    Request(CommitRequest),
+    Response(CommitResponse),
+
+    // Client -> Daemon: "Which repositories may I commit to?"
+    ListRepos,
+    // Daemon -> Client: the repositories the client may use.
+    RepoList(Vec<RepoInfo>),
+    // Daemon -> Client: the client isn't paired, or its trust has expired. Commit
+    // requests get a `Forbidden` response instead.
+    Denied { reason: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Handshake {
+    pub version: u32,
+    pub capabilities: Vec<Capability>,
+}
+
+// Features a daemon may or may not support. A request that needs one the daemon
+// lacks is refused on the client, before it is sent.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    // More than one change, or deletes and renames, in a single commit.
+    MultiFile,
+    // Committing to a named branch, and creating it from another revision.
+    Branches,
+    // Pushing to the repository's remote after committing.
+    Push,
+    // Listing the repositories in the daemon's registry.
+    ListRepos,
+    // Sent by a newer peer; not something this side knows about.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommitRequest {
+    // Alias of the repository in the daemon's registry.
+    pub repo: String,
+    // Every file in this list is committed together in a single commit.
+    pub changes: Vec<FileChange>,
+    pub commit_message: String,
+    // Branch to commit to. When missing, the daemon uses the repository's default
+    // branch, or whatever HEAD is if it has none.
+    pub branch: Option<String>,
+    // Revision to create `branch` from if it doesn't exist yet.
+    pub create_branch_from: Option<String>,
+    // Commit the changes were based on. The daemon rejects the request with a
+    // conflict if the branch has moved since.
+    pub expected_parent: Option<String>,
+    // Push the branch to the repository's configured remote after committing.
+    #[serde(default)]
+    pub push: bool,
+}
+
+// A single operation inside a commit request
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum FileChange {
+    Write { path: String, content: String },
+    Delete { path: String },
+    Rename { from: String, to: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommitResponse {
+    pub success: bool,
+    pub commit_hash: Option<String>,
+    pub error_message: Option<String>,
+    pub error_kind: Option<ErrorKind>,
+    // Outcome of the push, reported separately because the commit exists even if the push fails.
+    // `None` when no push was requested.
+    pub push_result: Option<PushResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PushResult {
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+// Lets clients tell failures apart without parsing `error_message`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    // The branch moved since the client read it; nothing was committed.
+    Conflict,
+    // A file path would leave the repository or touch `.git`.
+    InvalidPath,
+    // The client isn't allowed to do this, e.g. the repository isn't in the daemon's allow-list.
+    Forbidden,
+    Failed,
+}
+
+// A repository from the daemon's registry, as shown to clients
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RepoInfo {
+    pub alias: String,
+    pub default_branch: Option<String>,
+    // Whether the daemon has a remote configured to push this repository to.
+    pub can_push: bool,
+}
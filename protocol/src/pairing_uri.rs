@@ -0,0 +1,177 @@
+// The `remote-commit://` URI a daemon shows (usually as a QR code) so a client can pair
+// without anyone typing a multiaddr:
+//
+//   remote-commit://<daemon peer id>?addr=/ip4/192.168.1.20/tcp/4001&addr=...&code=ABCD-EFGH
+//
+// A daemon on a private network (pnet) adds `&psk=<64 hex characters>`, since the client
+// can't even connect without the key.
+//
+// Multiaddrs only use characters that are allowed in a query string as they are,
+// so nothing in the URI is percent-encoded.
+use libp2p::{multiaddr::Protocol, pnet::PreSharedKey, Multiaddr, PeerId};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::{parse_pre_shared_key, pre_shared_key_hex};
+
+pub const PAIRING_URI_SCHEME: &str = "remote-commit://";
+
+#[derive(Debug, Error)]
+#[error("Invalid pairing URI: {0}")]
+pub struct InvalidPairingUri(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairingUri {
+    pub peer_id: PeerId,
+    // Where the daemon listens, without the trailing `/p2p/<peer id>`
+    pub addresses: Vec<Multiaddr>,
+    // One-time pairing code, see `NetworkMessage::PairRequest`
+    pub code: String,
+    // Key of the daemon's private network, if it runs one
+    pub pre_shared_key: Option<PreSharedKey>,
+}
+
+impl PairingUri {
+    // The daemon addresses with `/p2p/<peer id>` appended, ready to dial
+    pub fn full_addresses(&self) -> Vec<Multiaddr> {
+        self.addresses
+            .iter()
+            .map(|address| address.clone().with(Protocol::P2p(self.peer_id.into())))
+            .collect()
+    }
+}
+
+impl fmt::Display for PairingUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}?", PAIRING_URI_SCHEME, self.peer_id)?;
+        for address in &self.addresses {
+            write!(f, "addr={}&", address)?;
+        }
+        write!(f, "code={}", self.code)?;
+        if let Some(key) = &self.pre_shared_key {
+            write!(f, "&psk={}", pre_shared_key_hex(key))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PairingUri {
+    type Err = InvalidPairingUri;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let rest = uri
+            .trim()
+            .strip_prefix(PAIRING_URI_SCHEME)
+            .ok_or_else(|| InvalidPairingUri(format!("it must start with {}", PAIRING_URI_SCHEME)))?;
+        let (peer_id, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let peer_id = PeerId::from_str(peer_id.trim_end_matches('/'))
+            .map_err(|e| InvalidPairingUri(format!("bad peer id: {}", e)))?;
+
+        let mut addresses = Vec::new();
+        let mut code = None;
+        let mut pre_shared_key = None;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some(("addr", value)) => {
+                    let mut address: Multiaddr = value
+                        .parse()
+                        .map_err(|e| InvalidPairingUri(format!("bad address {}: {}", value, e)))?;
+                    // Accept addresses that already name the daemon, as long as it's the same one
+                    if let Some(Protocol::P2p(hash)) = address.iter().last() {
+                        if PeerId::from_multihash(hash) != Ok(peer_id) {
+                            return Err(InvalidPairingUri(format!("address {} names another peer", value)));
+                        }
+                        address.pop();
+                    }
+                    addresses.push(address);
+                }
+                Some(("code", value)) if !value.is_empty() => code = Some(value.to_string()),
+                Some(("psk", value)) => {
+                    let key = parse_pre_shared_key(value)
+                        .map_err(|_| InvalidPairingUri("the pre-shared key must be 64 hex characters".to_string()))?;
+                    pre_shared_key = Some(key);
+                }
+                // Leave room for parameters newer daemons may add
+                _ => {}
+            }
+        }
+        if addresses.is_empty() {
+            return Err(InvalidPairingUri("it has no daemon address".to_string()));
+        }
+        let code = code.ok_or_else(|| InvalidPairingUri("it has no pairing code".to_string()))?;
+        Ok(Self { peer_id, addresses, code, pre_shared_key })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAEMON: &str = "12D3KooWMzd2tGd9pWxQDQz6C9cy9QHaGLndPMLNjC7avBGJrp4F";
+    const OTHER_PEER: &str = "QmaCpDMGvV2BGHeYERUEnRQAwe3N8SzbUtfsmvsqQLuvuJ";
+
+    fn pairing_uri(pre_shared_key: Option<PreSharedKey>) -> PairingUri {
+        PairingUri {
+            peer_id: DAEMON.parse().unwrap(),
+            addresses: vec![
+                "/ip4/192.168.1.20/tcp/35281".parse().unwrap(),
+                "/ip4/192.168.1.20/udp/35281/quic-v1".parse().unwrap(),
+            ],
+            code: "ABCD-EFGH".to_string(),
+            pre_shared_key,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_a_string() {
+        let uri = pairing_uri(None);
+        assert_eq!(uri.to_string().parse::<PairingUri>().unwrap(), uri);
+    }
+
+    #[test]
+    fn round_trips_with_a_pre_shared_key() {
+        let uri = pairing_uri(Some(PreSharedKey::new([7; 32])));
+        let text = uri.to_string();
+        assert!(text.ends_with(&format!("&psk={}", "07".repeat(32))));
+        assert_eq!(text.parse::<PairingUri>().unwrap(), uri);
+    }
+
+    #[test]
+    fn full_addresses_name_the_daemon() {
+        let full: Vec<String> = pairing_uri(None).full_addresses().iter().map(|a| a.to_string()).collect();
+        assert_eq!(full, [
+            format!("/ip4/192.168.1.20/tcp/35281/p2p/{}", DAEMON),
+            format!("/ip4/192.168.1.20/udp/35281/quic-v1/p2p/{}", DAEMON),
+        ]);
+    }
+
+    #[test]
+    fn accepts_addresses_that_already_name_the_daemon() {
+        let text = format!("remote-commit://{}?addr=/ip4/10.0.0.1/tcp/4001/p2p/{}&code=X", DAEMON, DAEMON);
+        let uri: PairingUri = text.parse().unwrap();
+        assert_eq!(uri.addresses, vec!["/ip4/10.0.0.1/tcp/4001".parse::<Multiaddr>().unwrap()]);
+    }
+
+    #[test]
+    fn ignores_unknown_parameters() {
+        let text = format!("remote-commit://{}?addr=/ip4/10.0.0.1/tcp/4001&v=2&code=X", DAEMON);
+        assert_eq!(text.parse::<PairingUri>().unwrap().code, "X");
+    }
+
+    #[test]
+    fn rejects_malformed_uris() {
+        for text in [
+            format!("http://{}?addr=/ip4/10.0.0.1/tcp/4001&code=X", DAEMON),
+            "remote-commit://not-a-peer?addr=/ip4/10.0.0.1/tcp/4001&code=X".to_string(),
+            format!("remote-commit://{}?code=X", DAEMON),
+            format!("remote-commit://{}?addr=/ip4/10.0.0.1/tcp/4001", DAEMON),
+            format!("remote-commit://{}?addr=/ip4/10.0.0.1/tcp/4001&code=", DAEMON),
+            format!("remote-commit://{}?addr=nonsense&code=X", DAEMON),
+            format!("remote-commit://{}?addr=/ip4/10.0.0.1/tcp/4001/p2p/{}&code=X", DAEMON, OTHER_PEER),
+            format!("remote-commit://{}?addr=/ip4/10.0.0.1/tcp/4001&code=X&psk=abcd", DAEMON),
+        ] {
+            assert!(text.parse::<PairingUri>().is_err(), "{} should be rejected", text);
+        }
+    }
+}
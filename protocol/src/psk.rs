@@ -0,0 +1,42 @@
+// Pre-shared keys for libp2p private networks (pnet). Outside a swarm.key file they are
+// written as 64 hex characters, e.g. in the daemon config and in pairing URIs.
+use libp2p::pnet::{KeyParseError, PreSharedKey};
+
+const SWARM_KEY_HEADER: &str = "/key/swarm/psk/1.0.0/\n/base16/\n";
+
+pub fn parse_pre_shared_key(hex: &str) -> Result<PreSharedKey, KeyParseError> {
+    // `PreSharedKey` only parses the swarm.key format, so give the hex its header
+    format!("{}{}", SWARM_KEY_HEADER, hex.trim()).parse()
+}
+
+pub fn pre_shared_key_hex(key: &PreSharedKey) -> String {
+    // The key itself is the last line of the swarm.key format
+    key.to_string().lines().last().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_HEX: &str = "6c2f3ae4b8a0d51f9e7b02c4d6a8e0f1324b5c6d7e8f90a1b2c3d4e5f6071829";
+
+    #[test]
+    fn hex_round_trips() {
+        let key = parse_pre_shared_key(KEY_HEX).unwrap();
+        assert_eq!(pre_shared_key_hex(&key), KEY_HEX);
+        assert_eq!(parse_pre_shared_key(&pre_shared_key_hex(&key)).unwrap(), key);
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_ignored() {
+        let key = parse_pre_shared_key(&format!("  {}\n", KEY_HEX)).unwrap();
+        assert_eq!(pre_shared_key_hex(&key), KEY_HEX);
+    }
+
+    #[test]
+    fn keys_of_the_wrong_length_or_alphabet_are_rejected() {
+        for hex in ["", &KEY_HEX[..62], &format!("{}00", KEY_HEX), &KEY_HEX.replace('6', "g")] {
+            assert!(parse_pre_shared_key(hex).is_err(), "{:?} should be rejected", hex);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// A circuit relay to self-host for daemons behind NAT. The daemon reserves a slot here
+// (network.relays in its config), clients dial it through the relay, and DCUtR then tries
+// to replace the relayed connection with a direct one. The relay also answers AutoNAT
+// dial-back requests, so daemons can tell whether they need it at all.
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use libp2p::{
+    autonat, identify, relay,
+    pnet::PreSharedKey,
+    swarm::{AddressScore, NetworkBehaviour, SwarmBuilder, SwarmEvent},
+    Multiaddr, PeerId,
+};
+use p2p_node::{build_transport, keystore};
+use protocol::parse_pre_shared_key;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const KEYPAIR_FILE: &str = "relay_identity.key";
+
+#[derive(NetworkBehaviour)]
+#[behaviour(to_swarm = "RelayBehaviourEvent")]
+struct RelayBehaviour {
+    relay: relay::Behaviour,
+    identify: identify::Behaviour,
+    autonat: autonat::Behaviour,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = pico_args::Arguments::from_env();
+    // Where the relay keeps its keypair, so its address stays the same across restarts
+    let data_dir: PathBuf = args.opt_value_from_str("--data-dir")?.unwrap_or_else(|| PathBuf::from("."));
+    // Repeat `--listen` to listen on several addresses
+    let mut listen_addresses: Vec<Multiaddr> = args.values_from_str("--listen")?;
+    // Addresses the relay is reachable at from outside, e.g. behind port forwarding
+    let external_addresses: Vec<Multiaddr> = args.values_from_str("--external")?;
+    // Only peers with the key can connect. QUIC can't carry one, so it stays off then.
+    let pre_shared_key: Option<PreSharedKey> = args
+        .opt_value_from_str::<_, String>("--pre-shared-key")?
+        .map(|hex| parse_pre_shared_key(&hex).map_err(|_| anyhow::anyhow!("--pre-shared-key must be 64 hex characters")))
+        .transpose()?;
+    // libp2p's defaults of 2 minutes and 128 KiB per circuit are too little for a commit
+    // with a few files, so allow a full request and response by default
+    let max_circuit_duration: humantime::Duration = args
+        .opt_value_from_str("--max-circuit-duration")?
+        .unwrap_or_else(|| Duration::from_secs(10 * 60).into());
+    let max_circuit_bytes: u64 = args.opt_value_from_str("--max-circuit-bytes")?.unwrap_or(32 * 1024 * 1024);
+    // AutoNAT only dials back public addresses, unless we are testing on one machine or a LAN
+    let allow_private_addresses = args.contains("--allow-private-addresses");
+    let log_level: String = args.opt_value_from_str("--log-level")?.unwrap_or_else(|| "warn".to_string());
+    let unexpected = args.finish();
+    if !unexpected.is_empty() {
+        return Err(anyhow::anyhow!("Unexpected arguments: {:?}", unexpected));
+    }
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(&log_level)).init();
+
+    if listen_addresses.is_empty() {
+        listen_addresses.push("/ip4/0.0.0.0/tcp/4001".parse()?);
+        if pre_shared_key.is_none() {
+            listen_addresses.push("/ip4/0.0.0.0/udp/4001/quic-v1".parse()?);
+        }
+    }
+
+    let id_keys = keystore::get_or_create_identity(&data_dir, KEYPAIR_FILE)?;
+    let local_peer_id = PeerId::from(id_keys.public());
+    println!("Relay Peer ID: {}", local_peer_id);
+    if let Some(key) = &pre_shared_key {
+        println!("Private network key fingerprint: {}", key.fingerprint());
+    }
+
+    let transport = build_transport(&id_keys, None, pre_shared_key, true)?;
+    let relay_config = relay::Config {
+        max_circuit_duration: max_circuit_duration.into(),
+        max_circuit_bytes,
+        ..Default::default()
+    };
+    let autonat_config = autonat::Config {
+        only_global_ips: !allow_private_addresses,
+        ..Default::default()
+    };
+    let behaviour = RelayBehaviour {
+        relay: relay::Behaviour::new(local_peer_id, relay_config),
+        identify: identify::Behaviour::new(identify::Config::new(
+            "/remote-commit-relay/1.0".to_string(),
+            id_keys.public(),
+        )),
+        autonat: autonat::Behaviour::new(local_peer_id, autonat_config),
+    };
+    let mut swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id).build();
+
+    for address in &listen_addresses {
+        swarm.listen_on(address.clone())
+            .with_context(|| format!("Failed to listen on {}", address))?;
+    }
+    // Reservations tell daemons these addresses, which is what clients end up dialing
+    for address in external_addresses {
+        println!("Relay reachable at {}/p2p/{}", address, local_peer_id);
+        swarm.add_external_address(address, AddressScore::Infinite);
+    }
+
+    loop {
+        match swarm.select_next_some().await {
+            SwarmEvent::NewListenAddr { address, .. } => {
+                println!("Relay listening on {}/p2p/{}", address, local_peer_id);
+            }
+            SwarmEvent::Behaviour(RelayBehaviourEvent::Relay(relay::Event::ReservationReqAccepted {
+                src_peer_id,
+                renewed: false,
+            })) => {
+                println!("[Relay] Reserved a slot for {}", src_peer_id);
+            }
+            SwarmEvent::Behaviour(RelayBehaviourEvent::Relay(relay::Event::ReservationTimedOut { src_peer_id })) => {
+                println!("[Relay] Reservation of {} expired", src_peer_id);
+            }
+            SwarmEvent::Behaviour(RelayBehaviourEvent::Relay(relay::Event::CircuitReqAccepted {
+                src_peer_id,
+                dst_peer_id,
+            })) => {
+                println!("[Relay] Relaying from {} to {}", src_peer_id, dst_peer_id);
+            }
+            SwarmEvent::Behaviour(RelayBehaviourEvent::Relay(relay::Event::CircuitReqDenied {
+                src_peer_id,
+                dst_peer_id,
+            })) => {
+                // Usually the destination has no reservation, or the limits are reached
+                println!("[Relay] Refused to relay from {} to {}", src_peer_id, dst_peer_id);
+            }
+            _ => {}
+        }
+    }
+}